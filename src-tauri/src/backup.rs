@@ -0,0 +1,63 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::{migrate_entry, validate_settings, ClipboardEntry};
+
+/// Layout version of the archive; bump on incompatible changes
+const BACKUP_VERSION: u32 = 1;
+
+/// Everything needed to move Recall to another machine, as one JSON document.
+/// Pinned entries are part of `history`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    #[serde(with = "crate::timestamp")]
+    pub created: DateTime<Local>,
+    pub settings: Settings,
+    pub history: Vec<ClipboardEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub total_entries: usize,
+    pub pinned_entries: usize,
+}
+
+impl Backup {
+    pub fn new(settings: Settings, history: Vec<ClipboardEntry>) -> Self {
+        Backup {
+            version: BACKUP_VERSION,
+            created: Local::now(),
+            settings,
+            history,
+        }
+    }
+
+    pub fn summary(&self) -> BackupSummary {
+        BackupSummary {
+            total_entries: self.history.len(),
+            pinned_entries: self.history.iter().filter(|e| e.pinned).count(),
+        }
+    }
+}
+
+/// Parses and validates a whole archive without touching the current state.
+/// Entries written by older versions are migrated to the current schema.
+pub fn parse_backup(json: &str) -> Result<Backup, String> {
+    let mut backup: Backup =
+        serde_json::from_str(json).map_err(|e| format!("Invalid backup file: {}", e))?;
+
+    if backup.version > BACKUP_VERSION {
+        return Err(format!(
+            "Backup version {} is newer than supported version {}",
+            backup.version, BACKUP_VERSION
+        ));
+    }
+    validate_settings(&backup.settings)?;
+
+    for entry in backup.history.iter_mut() {
+        migrate_entry(entry);
+    }
+    Ok(backup)
+}
@@ -0,0 +1,275 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// What kind of text an entry holds, as detected by `classify_content`.
+/// The variant order is the display order used by the UI.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum ContentKind {
+    #[default]
+    Text,
+    Url,
+    Code,
+    /// Paths of files copied in a file manager, one per line. Set at capture
+    /// time rather than detected from the text.
+    Files,
+}
+
+impl ContentKind {
+    /// Every kind, in display order
+    pub const ALL: [ContentKind; 4] = [
+        ContentKind::Text,
+        ContentKind::Url,
+        ContentKind::Code,
+        ContentKind::Files,
+    ];
+}
+
+/// Line prefixes that are a strong hint the text is source code
+const CODE_PREFIXES: &[&str] = &[
+    "fn ",
+    "pub ",
+    "let ",
+    "const ",
+    "var ",
+    "def ",
+    "class ",
+    "function ",
+    "import ",
+    "return ",
+    "#include",
+    "#!",
+    "//",
+    "if (",
+    "for (",
+    "while (",
+    "} else",
+];
+
+pub fn classify_content(content: &str) -> ContentKind {
+    if is_url(content.trim()) {
+        ContentKind::Url
+    } else if looks_like_code(content) {
+        ContentKind::Code
+    } else {
+        ContentKind::Text
+    }
+}
+
+/// Re-indents JSON with two spaces per level. Unlike a parse and re-serialize
+/// round trip, key order and number formatting stay exactly as written.
+pub fn pretty_json(text: &str) -> Result<String, String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                // Empty containers stay on one line
+                match chars.peek() {
+                    Some(&close @ ('}' | ']')) => {
+                        out.push(close);
+                        chars.next();
+                    }
+                    _ => {
+                        depth += 1;
+                        push_newline(&mut out, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                push_newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                push_newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn push_newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(depth));
+}
+
+/// Every http/https URL in `text`, in order of appearance. Trailing
+/// punctuation and unbalanced closing parentheses are not part of the URL.
+pub fn find_urls(text: &str) -> Vec<&str> {
+    static URL_PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = URL_PATTERN.get_or_init(|| Regex::new(r#"https?://[^\s<>"'`\[\]{}]+"#).unwrap());
+
+    pattern
+        .find_iter(text)
+        .map(|m| trim_url_end(m.as_str()))
+        .filter(|url| {
+            url.split_once("://")
+                .is_some_and(|(_, rest)| !rest.is_empty())
+        })
+        .collect()
+}
+
+/// Strips sentence punctuation that follows a URL in prose, e.g. the `.` in
+/// "see https://example.com." or the `)` in "(https://example.com)"
+fn trim_url_end(mut url: &str) -> &str {
+    loop {
+        if let Some(stripped) = url.strip_suffix(['.', ',', ';', ':', '!', '?']) {
+            url = stripped;
+        } else if url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+            url = &url[..url.len() - 1];
+        } else {
+            return url;
+        }
+    }
+}
+
+fn is_url(text: &str) -> bool {
+    ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|scheme| text.starts_with(scheme) && text.len() > scheme.len())
+        && !text.contains(char::is_whitespace)
+}
+
+/// Heuristic: at least half of the non-empty lines look like code
+/// (statement terminators, braces, comments or common keywords).
+fn looks_like_code(content: &str) -> bool {
+    let lines: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return false;
+    }
+
+    let code_lines = lines
+        .iter()
+        .filter(|l| {
+            l.ends_with(';')
+                || l.ends_with('{')
+                || l.ends_with('}')
+                || l.contains("=>")
+                || CODE_PREFIXES.iter().any(|p| l.starts_with(p))
+        })
+        .count();
+
+    code_lines * 2 >= lines.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_url_in_order() {
+        let text = "docs at https://example.com/a and http://example.org/b?q=1#x";
+        assert_eq!(
+            find_urls(text),
+            ["https://example.com/a", "http://example.org/b?q=1#x"]
+        );
+    }
+
+    #[test]
+    fn trailing_punctuation_is_not_part_of_the_url() {
+        for suffix in [".", ",", ";", ":", ")", ").", "...", ":)"] {
+            let text = format!("see https://example.com/page{}", suffix);
+            assert_eq!(find_urls(&text), ["https://example.com/page"], "{}", suffix);
+        }
+    }
+
+    #[test]
+    fn balanced_parentheses_stay_in_the_url() {
+        let url = "https://en.wikipedia.org/wiki/Rust_(programming_language)";
+        assert_eq!(find_urls(url), [url]);
+        assert_eq!(find_urls(&format!("({})", url)), [url]);
+        assert_eq!(find_urls(&format!("({}).", url)), [url]);
+    }
+
+    #[test]
+    fn text_without_urls_has_none() {
+        assert!(find_urls("no links here, just example.com and ftp").is_empty());
+        assert!(find_urls("a bare scheme https:// is not a link").is_empty());
+        assert!(find_urls("").is_empty());
+    }
+
+    #[test]
+    fn pretty_json_indents_nested_values() {
+        let pretty = pretty_json(r#"{"a":{"b":[1,2.50]},"c":true}"#).unwrap();
+        assert_eq!(
+            pretty,
+            "{\n  \"a\": {\n    \"b\": [\n      1,\n      2.50\n    ]\n  },\n  \"c\": true\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_json_keeps_key_order_and_reindents() {
+        let pretty = pretty_json("{\n\t\"b\" : 1 ,\n\t\"a\" : 2\n}").unwrap();
+        assert_eq!(pretty, "{\n  \"b\": 1,\n  \"a\": 2\n}");
+    }
+
+    #[test]
+    fn pretty_json_keeps_empty_containers_on_one_line() {
+        assert_eq!(pretty_json("{}").unwrap(), "{}");
+        assert_eq!(pretty_json("[ ]").unwrap(), "[]");
+        assert_eq!(
+            pretty_json(r#"{"a":[],"b":{ }}"#).unwrap(),
+            "{\n  \"a\": [],\n  \"b\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_json_leaves_strings_alone() {
+        let pretty = pretty_json(r#"{"s":"say \"hi\" {[ , : ]}","p":"C:\\"}"#).unwrap();
+        assert_eq!(
+            pretty,
+            "{\n  \"s\": \"say \\\"hi\\\" {[ , : ]}\",\n  \"p\": \"C:\\\\\"\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_json_keeps_unicode() {
+        assert_eq!(
+            pretty_json(r#"{"名前":["🍣","\u00e9"]}"#).unwrap(),
+            "{\n  \"名前\": [\n    \"🍣\",\n    \"\\u00e9\"\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn pretty_json_rejects_invalid_json() {
+        assert!(pretty_json(r#"{"a":}"#).is_err());
+        assert!(pretty_json("not json").is_err());
+    }
+}
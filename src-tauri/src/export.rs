@@ -0,0 +1,42 @@
+use crate::ClipboardEntry;
+
+/// Renders entries as a numbered Markdown list with timestamps.
+/// Single-line content follows the timestamp; multi-line content is placed in
+/// a fenced code block under the list item. Notes follow as a blockquote.
+pub fn render_markdown(entries: &[ClipboardEntry]) -> String {
+    let mut markdown = String::from("# Clipboard History\n\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let marker = format!("{}. ", i + 1);
+        let timestamp = entry.timestamp.format("%Y-%m-%d %H:%M:%S");
+
+        if entry.content.contains('\n') {
+            let indent = " ".repeat(marker.len());
+            let fence = code_fence(&entry.content);
+            markdown.push_str(&format!("{}{}\n", marker, timestamp));
+            markdown.push_str(&format!("{}{}\n", indent, fence));
+            for line in entry.content.lines() {
+                markdown.push_str(&format!("{}{}\n", indent, line));
+            }
+            markdown.push_str(&format!("{}{}\n", indent, fence));
+        } else {
+            markdown.push_str(&format!("{}{} — {}\n", marker, timestamp, entry.content));
+        }
+
+        if let Some(note) = &entry.note {
+            let indent = " ".repeat(marker.len());
+            for line in note.lines() {
+                markdown.push_str(&format!("{}> {}\n", indent, line));
+            }
+        }
+    }
+
+    markdown
+}
+
+/// A backtick fence longer than any backtick run inside `content`, so the
+/// content can't close the block early
+fn code_fence(content: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
@@ -1,14 +1,38 @@
+mod backup;
+mod content;
+mod export;
+mod logger;
+mod settings;
+mod shortcut;
+mod timestamp;
+mod trash;
+
 use arboard::Clipboard;
 use chrono::{DateTime, Local};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::thread;
-use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter, Listener, LogicalPosition, Manager};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Listener, LogicalPosition, LogicalSize, Manager};
+use tauri_plugin_shell::ShellExt;
+
+use backup::{Backup, BackupSummary};
+use content::{classify_content, find_urls, pretty_json, ContentKind};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use settings::{current_settings, save_settings, EvictionPolicy, Settings};
+#[cfg(target_os = "macos")]
+use settings::{PopupPosition, TriggerMode};
+use shortcut::Shortcut;
+use similar::TextDiff;
+use trash::TrashedEntry;
 
 #[cfg(target_os = "macos")]
 use block2::StackBlock;
@@ -20,15 +44,94 @@ use objc2_app_kit::{
 use objc2_foundation::NSRunLoop;
 #[cfg(target_os = "macos")]
 use std::ptr::NonNull;
-#[cfg(target_os = "macos")]
-use std::sync::Mutex;
+
+/// Current on-disk format version of a history line.
+///
+/// - v1: `timestamp`, `content` and `pinned`. Lines written before versioning
+///   have no `v` field and are read as v1.
+/// - v2: adds the per-entry `v` field and a stable `id`.
+/// - v3: adds the detected content `kind`.
+/// - v4: timestamps are stored in UTC instead of the local offset.
+///
+/// Optional fields (e.g. `html`) default to empty when missing and don't need
+/// a version bump.
+///
+/// Older lines are upgraded by `migrate_entry` when the history is loaded.
+const SCHEMA_VERSION: u32 = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardEntry {
+    #[serde(default = "legacy_schema_version")]
+    pub v: u32,
+    #[serde(default)]
+    pub id: String,
+    #[serde(with = "timestamp")]
     pub timestamp: DateTime<Local>,
     pub content: String,
     #[serde(default)]
     pub pinned: bool,
+    #[serde(default)]
+    pub kind: ContentKind,
+    /// HTML flavor of the copied content, when the source app provided one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Added by hand via `add_manual_entry` rather than captured
+    #[serde(default)]
+    pub manual: bool,
+    /// Name of the frontmost application when the entry was captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_app: Option<String>,
+    /// When the entry was last pinned; absent for entries pinned before this
+    /// was recorded
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pinned_at: Option<DateTime<Local>>,
+    /// When the entry was last copied back out of the history
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_used: Option<DateTime<Local>>,
+    /// Short user-given title shown instead of the content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Longer free-form annotation, e.g. what the snippet is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// The content was replaced by `MASKED_CONTENT` because it matches a
+    /// `mask_patterns` entry; `reveal_entry` returns the real content. Only
+    /// ever set on entries handed to the UI, never on stored ones.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub masked: bool,
+}
+
+impl ClipboardEntry {
+    fn new(content: String) -> Self {
+        ClipboardEntry {
+            v: SCHEMA_VERSION,
+            id: new_entry_id(),
+            timestamp: Local::now(),
+            kind: classify_content(&content),
+            content,
+            pinned: false,
+            html: None,
+            manual: false,
+            source_app: None,
+            pinned_at: None,
+            last_used: None,
+            label: None,
+            note: None,
+            masked: false,
+        }
+    }
+}
+
+fn legacy_schema_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,60 +140,346 @@ pub struct HistoryResponse {
     pub max_entries: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryStats {
+    pub total_entries: usize,
+    pub pinned_entries: usize,
+    /// Sum of the UTF-8 byte lengths of all entry contents
+    pub content_bytes: usize,
+    /// Size of the history file on disk
+    pub file_bytes: u64,
+    pub oldest: Option<DateTime<Local>>,
+    pub newest: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub entry: ClipboardEntry,
+    /// Fuzzy match score (higher is better); 0 for substring search
+    pub score: i64,
+    /// Char indices into `entry.content` matched by the fuzzy matcher
+    pub indices: Vec<usize>,
+}
+
+/// Result of `verify_history`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryReport {
+    pub valid_lines: usize,
+    /// Lines that don't parse as an entry; `load_history` silently skips them
+    pub invalid_lines: usize,
+    /// Entries whose content repeats a newer entry's
+    pub duplicate_entries: usize,
+    /// Entries beyond the history limit
+    pub over_cap: usize,
+    /// Whether a cleaned-up file was written
+    pub repaired: bool,
+}
+
+/// Content stored in more than one history entry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Shortened for display; use `ids` to act on the entries
+    pub preview: String,
+    pub count: usize,
+    /// Ids of the entries with this content, oldest first
+    pub ids: Vec<String>,
+}
+
+/// Conversions that can be applied to an entry's content when copying it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Transform {
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+    PrettyJson,
+}
+
 const MAX_HISTORY_ENTRIES: usize = 200;
+/// Upper bound on the compiled size of user-supplied search regexes
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+/// Source app name reported for entries captured without one
+const UNKNOWN_SOURCE_APP: &str = "Unknown";
 const DOUBLE_TAP_THRESHOLD_MS: u128 = 400;
+/// Minimum time between capture notifications, so rapid copies don't spam
+const CAPTURE_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(5);
+/// Smallest window size the `window_width`/`window_height` settings can request
+const MIN_WINDOW_WIDTH: u32 = 300;
+const MIN_WINDOW_HEIGHT: u32 = 200;
+/// Range of font sizes the `popup_font_size` setting accepts, in pixels
+const POPUP_FONT_SIZES: std::ops::RangeInclusive<u32> = 8..=32;
+
+/// Virtual key codes of the 1-9 keys on the top row (kVK_ANSI_1..kVK_ANSI_9),
+/// indexed by slot
+#[cfg(target_os = "macos")]
+const SLOT_KEY_CODES: [u16; 9] = [0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+/// Virtual key code of the V key (kVK_ANSI_V)
+#[cfg(target_os = "macos")]
+const KEY_CODE_V: u16 = 0x09;
+/// Virtual key codes of the Return and Tab keys (kVK_Return, kVK_Tab)
+#[cfg(target_os = "macos")]
+const KEY_CODE_RETURN: u16 = 0x24;
+#[cfg(target_os = "macos")]
+const KEY_CODE_TAB: u16 = 0x30;
+/// Pause after each keystroke when pasting by typing, so apps keep up
+#[cfg(target_os = "macos")]
+const TYPING_KEY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Held while typing an entry, so a second paste-as-typing waits for the
+/// first to finish instead of interleaving its keystrokes
+static TYPING: Mutex<()> = Mutex::new(());
+
+/// Entries removed by the most recent delete/clear, for `undo_last_removal`
+static UNDO_BUFFER: Mutex<Vec<ClipboardEntry>> = Mutex::new(Vec::new());
+
+/// Kill-ring style queue filled by `get_paste_queue` and consumed by `next_in_queue`
+struct PasteQueue {
+    entries: VecDeque<ClipboardEntry>,
+    /// Content the queue last put on the clipboard, so the monitor's capture
+    /// of it doesn't count as new content
+    last_copied: Option<String>,
+}
+
+static PASTE_QUEUE: Mutex<PasteQueue> = Mutex::new(PasteQueue {
+    entries: VecDeque::new(),
+    last_copied: None,
+});
+
+/// Whether clipboard capture is on, and whether the monitor thread is alive.
+/// Kept under one lock so re-enabling never races a thread that is exiting.
+struct MonitorState {
+    enabled: bool,
+    thread_alive: bool,
+    /// Clipboard text last seen by the monitor or `capture_now`
+    last_content: Option<String>,
+    /// Why the last capture failed; cleared by the next successful one
+    last_error: Option<String>,
+}
+
+static MONITOR: Mutex<MonitorState> = Mutex::new(MonitorState {
+    enabled: false,
+    thread_alive: false,
+    last_content: None,
+    last_error: None,
+});
 
 #[cfg(target_os = "macos")]
 static PREVIOUS_APP: Mutex<Option<objc2::rc::Retained<NSRunningApplication>>> = Mutex::new(None);
 
-fn get_data_dir() -> PathBuf {
-    let data_dir = dirs::data_local_dir()
+fn new_entry_id() -> String {
+    // Timestamps alone can collide when several entries are created (or
+    // migrated) within the same instant, so a process-wide counter is mixed in.
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = Local::now().timestamp_nanos_opt().unwrap_or_default();
+    let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", nanos, seq)
+}
+
+/// Upgrades an entry read from disk to `SCHEMA_VERSION`.
+/// Returns true if the entry was changed and the file needs rewriting.
+/// Entries written by a newer version are left untouched.
+fn migrate_entry(entry: &mut ClipboardEntry) -> bool {
+    if entry.v >= SCHEMA_VERSION {
+        return false;
+    }
+
+    // v1 -> v2: assign a stable id
+    if entry.v < 2 && entry.id.is_empty() {
+        entry.id = new_entry_id();
+    }
+
+    // v2 -> v3: classify the content
+    if entry.v < 3 {
+        entry.kind = classify_content(&entry.content);
+    }
+
+    // v3 -> v4: nothing to change in memory; rewriting the file stores the
+    // timestamps in UTC
+
+    entry.v = SCHEMA_VERSION;
+    true
+}
+
+/// File in the default data directory holding the path of a data directory
+/// moved elsewhere by `migrate_data_dir`
+const DATA_DIR_POINTER: &str = "data_dir_location";
+
+/// The active data directory, resolved on first use
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn default_data_dir() -> PathBuf {
+    dirs::data_local_dir()
         .unwrap_or_else(|| PathBuf::from("."))
-        .join("recall");
+        .join("recall")
+}
+
+/// The default data directory, unless the pointer file names another one
+fn resolve_data_dir() -> PathBuf {
+    let default = default_data_dir();
+    fs::read_to_string(default.join(DATA_DIR_POINTER))
+        .ok()
+        .map(|path| PathBuf::from(path.trim()))
+        .filter(|path| path.is_absolute())
+        .unwrap_or(default)
+}
+
+fn get_data_dir() -> PathBuf {
+    let data_dir = DATA_DIR
+        .lock()
+        .unwrap()
+        .get_or_insert_with(resolve_data_dir)
+        .clone();
     fs::create_dir_all(&data_dir).ok();
     data_dir
 }
 
+/// `<base>.jsonl` in the data directory for the default (empty) profile,
+/// `<base>_<profile>.jsonl` for any other profile
+fn profile_data_path(base: &str) -> PathBuf {
+    profile_file(base, &current_settings().profile)
+}
+
+/// `profile_data_path` for a given profile rather than the active one
+fn profile_file(base: &str, profile: &str) -> PathBuf {
+    if profile.is_empty() {
+        get_data_dir().join(format!("{}.jsonl", base))
+    } else {
+        get_data_dir().join(format!("{}_{}.jsonl", base, profile))
+    }
+}
+
+/// The history file of the active profile. The default (empty) profile keeps
+/// the original `clipboard_history.jsonl` name.
 fn get_history_path() -> PathBuf {
-    get_data_dir().join("clipboard_history.jsonl")
+    profile_data_path("clipboard_history")
+}
+
+/// Pinned entries of the active profile, kept apart from the history file
+/// so they survive anything that happens to it
+fn get_favorites_path() -> PathBuf {
+    profile_data_path("favorites")
+}
+
+const INVALID_PROFILE_NAME: &str = "Profile names may only contain letters, digits, '-' and '_'";
+
+/// Profile names become part of a file name, so only allow a safe subset
+fn is_valid_profile_name(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// First line of `text`, shortened to `max_chars` characters with an ellipsis
+fn truncate_for_display(text: &str, max_chars: usize) -> String {
+    let first_line = text.trim().lines().next().unwrap_or("");
+    if first_line.chars().count() > max_chars {
+        let truncated: String = first_line.chars().take(max_chars).collect();
+        format!("{}…", truncated)
+    } else if text.trim().lines().nth(1).is_some() {
+        format!("{}…", first_line)
+    } else {
+        first_line.to_string()
+    }
+}
+
+/// Converts Windows (`\r\n`) and classic Mac (`\r`) line endings to `\n`,
+/// so the same text copied from different apps dedups to a single entry.
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Applies the capture-time settings to freshly read clipboard text.
+/// Returns `None` when the text should not be recorded at all.
+fn prepare_capture(text: &str, settings: &Settings) -> Option<String> {
+    // Whitespace-only captures are never useful history entries
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let mut content = if settings.normalize_line_endings {
+        normalize_line_endings(text)
+    } else {
+        text.to_string()
+    };
+
+    if settings.trim_on_capture {
+        content = content.trim().to_string();
+    }
+
+    // Counted in characters, so a single multi-byte character is still short
+    if content.chars().count() < settings.min_entry_chars {
+        return None;
+    }
+
+    Some(content)
+}
+
+/// Shown in place of content that matches a `mask_patterns` entry
+const MASKED_CONTENT: &str = "••••••••";
+
+/// Collapses every whitespace run to a single space and trims the ends
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// What `save_entry` compares to detect duplicates: the kind, so e.g. a file
+/// copy and the same text copied as text stay apart, and the content
+fn dedup_key<'a>(entry: &'a ClipboardEntry, settings: &Settings) -> (ContentKind, Cow<'a, str>) {
+    let content = &entry.content;
+    let key = if settings.collapse_whitespace_dupes {
+        Cow::Owned(collapse_whitespace(content))
+    } else {
+        Cow::Borrowed(content.as_str())
+    };
+    if settings.case_insensitive_dedup {
+        (entry.kind, Cow::Owned(key.to_lowercase()))
+    } else {
+        (entry.kind, key)
+    }
 }
 
 fn save_entry(entry: &ClipboardEntry) -> std::io::Result<()> {
     let mut history = load_history();
+    let settings = current_settings();
+    let key = dedup_key(entry, &settings);
 
-    // Check if the same content exists and preserve its pinned state
-    let existing_pinned = history
-        .iter()
-        .find(|e| e.content == entry.content)
-        .map(|e| e.pinned)
-        .unwrap_or(false);
-
-    history.retain(|e| e.content != entry.content);
+    // A quick follow-up write from the same app replaces its previous value
+    let collapsed = match history.last() {
+        Some(newest) if collapses_into(newest, entry, &settings) => history.pop(),
+        _ => None,
+    };
 
-    history.push(ClipboardEntry {
-        timestamp: entry.timestamp,
-        content: entry.content.clone(),
-        pinned: existing_pinned,
-    });
+    // Check if the same content exists and preserve its id and pinned state.
+    // The new entry's raw content replaces the old one.
+    let existing = history.iter().find(|e| dedup_key(e, &settings) == key);
+    let mut new_entry = entry.clone();
+    new_entry.v = SCHEMA_VERSION;
+    let is_duplicate = existing.is_some();
+    if let Some(existing) = existing {
+        new_entry.id = existing.id.clone();
+        new_entry.pinned = existing.pinned;
+        new_entry.pinned_at = existing.pinned_at;
+        new_entry.last_used = existing.last_used;
+        new_entry.label = existing.label.clone();
+        new_entry.note = existing.note.clone();
+        new_entry.manual |= existing.manual;
+    } else if let Some(collapsed) = &collapsed {
+        new_entry.id = collapsed.id.clone();
+    }
 
-    // Trim history while preserving pinned items
-    if history.len() > MAX_HISTORY_ENTRIES {
-        // Separate pinned and unpinned items
-        let pinned: Vec<_> = history.iter().filter(|e| e.pinned).cloned().collect();
-        let mut unpinned: Vec<_> = history.iter().filter(|e| !e.pinned).cloned().collect();
+    history.retain(|e| dedup_key(e, &settings) != key);
 
-        // Calculate how many unpinned items we can keep
-        let unpinned_limit = MAX_HISTORY_ENTRIES.saturating_sub(pinned.len());
+    // Fast path: unique content and no trim needed, so the file only grows
+    // by one line and doesn't have to be rewritten
+    if !is_duplicate && collapsed.is_none() && history.len() < MAX_HISTORY_ENTRIES {
+        return append_entry(&new_entry);
+    }
 
-        // Keep only the newest unpinned items
-        if unpinned.len() > unpinned_limit {
-            let start = unpinned.len() - unpinned_limit;
-            unpinned = unpinned.split_off(start);
-        }
+    history.push(new_entry);
 
-        // Rebuild history: unpinned first (older), then pinned
-        history = unpinned;
-        history.extend(pinned);
+    // Trim history while preserving pinned items
+    if history.len() > MAX_HISTORY_ENTRIES {
+        let excess = history.len() - MAX_HISTORY_ENTRIES;
+        evict_unpinned(&mut history, excess, settings.eviction_policy);
 
         // Sort by timestamp to maintain chronological order
         history.sort_by_key(|a| a.timestamp);
@@ -99,139 +488,2077 @@ fn save_entry(entry: &ClipboardEntry) -> std::io::Result<()> {
     save_history(&history)
 }
 
-fn save_history(history: &[ClipboardEntry]) -> std::io::Result<()> {
-    let path = get_history_path();
+/// Whether `entry` should replace `newest` rather than be added after it: the
+/// same source app wrote both within `collapse_consecutive_same_source_ms`.
+/// Pinned and manual entries are never replaced.
+fn collapses_into(newest: &ClipboardEntry, entry: &ClipboardEntry, settings: &Settings) -> bool {
+    let window_ms = settings.collapse_consecutive_same_source_ms;
+    let elapsed_ms = (entry.timestamp - newest.timestamp).num_milliseconds();
+    window_ms > 0
+        && !newest.pinned
+        && !newest.manual
+        && entry.source_app.is_some()
+        && newest.source_app == entry.source_app
+        && (0..=window_ms as i64).contains(&elapsed_ms)
+}
+
+/// Removes up to `count` unpinned entries, picking them by `policy`
+fn evict_unpinned(history: &mut Vec<ClipboardEntry>, count: usize, policy: EvictionPolicy) {
+    let mut candidates: Vec<(DateTime<Local>, usize)> = history
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !e.pinned)
+        .map(|(i, e)| match policy {
+            EvictionPolicy::Fifo => (e.timestamp, i),
+            EvictionPolicy::Lru => (last_used_time(e), i),
+        })
+        .collect();
+    candidates.sort();
+
+    let evicted: HashSet<usize> = candidates.into_iter().take(count).map(|(_, i)| i).collect();
+    let mut index = 0;
+    history.retain(|_| {
+        let keep = !evicted.contains(&index);
+        index += 1;
+        keep
+    });
+}
+
+/// When `entry` was last used: copied back out, or otherwise captured
+fn last_used_time(entry: &ClipboardEntry) -> DateTime<Local> {
+    entry
+        .last_used
+        .map_or(entry.timestamp, |used| used.max(entry.timestamp))
+}
+
+/// Held while writing any data file (history, favorites, trash, settings,
+/// logs), from resolving its path to finishing the write, and by
+/// `migrate_data_dir` for the whole move, so nothing is written to the old
+/// directory mid-move. Nothing may log while holding it, since the logger
+/// takes it too.
+static DATA_LOCK: Mutex<()> = Mutex::new(());
+
+fn append_entry(entry: &ClipboardEntry) -> std::io::Result<()> {
+    let _data = DATA_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_history_path())?;
+    let json = serde_json::to_string(entry)?;
+    writeln!(file, "{}", json)?;
+    remember_own_write(&get_history_path());
+    Ok(())
+}
+
+fn write_entries<T: Serialize>(path: &Path, entries: &[T]) -> std::io::Result<()> {
     let mut file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
-        .open(&path)?;
-    for e in history {
+        .open(path)?;
+    for e in entries {
         let json = serde_json::to_string(e)?;
         writeln!(file, "{}", json)?;
     }
+    remember_own_write(path);
     Ok(())
 }
 
-fn load_history() -> Vec<ClipboardEntry> {
-    let path = get_history_path();
-    let file = match fs::File::open(&path) {
+/// Modification time of each file right after the app last wrote it, so the
+/// file watcher can tell the app's own writes from outside edits
+static OWN_WRITES: Mutex<BTreeMap<PathBuf, SystemTime>> = Mutex::new(BTreeMap::new());
+
+fn remember_own_write(path: &Path) {
+    if let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) {
+        OWN_WRITES
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), modified);
+    }
+}
+
+/// Whether `path` still has the modification time of the app's last write
+fn is_own_write(path: &Path, modified: Option<SystemTime>) -> bool {
+    modified.is_some() && OWN_WRITES.lock().unwrap().get(path) == modified.as_ref()
+}
+
+/// Reads a JSONL entry file, skipping unparsable lines. A missing file is empty.
+fn read_entries<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    let file = match fs::File::open(path) {
         Ok(f) => f,
         Err(_) => return Vec::new(),
     };
-    let reader = BufReader::new(file);
-    reader
+    BufReader::new(file)
         .lines()
         .map_while(Result::ok)
         .filter_map(|line| serde_json::from_str(&line).ok())
         .collect()
 }
 
+fn save_history(history: &[ClipboardEntry]) -> std::io::Result<()> {
+    let _data = DATA_LOCK.lock().unwrap();
+    write_entries(&get_history_path(), history)
+}
+
+/// Copies the pinned entries of `history` to the favorites file. Only pin
+/// changes and explicit deletions call this; purges and clears never do.
+fn save_favorites(history: &[ClipboardEntry]) -> std::io::Result<()> {
+    let pinned: Vec<ClipboardEntry> = history.iter().filter(|e| e.pinned).cloned().collect();
+    let _data = DATA_LOCK.lock().unwrap();
+    write_entries(&get_favorites_path(), &pinned)
+}
+
+fn load_history() -> Vec<ClipboardEntry> {
+    let mut history: Vec<ClipboardEntry> = read_entries(&get_history_path());
+
+    // Favorites missing from the history file (e.g. lost to a bad write)
+    // are merged back in and written to the history file below
+    let mut missing_favorites = false;
+    for favorite in read_entries::<ClipboardEntry>(&get_favorites_path()) {
+        if !history
+            .iter()
+            .any(|e| e.id == favorite.id || e.content == favorite.content)
+        {
+            history.push(favorite);
+            missing_favorites = true;
+        }
+    }
+    if missing_favorites {
+        history.sort_by_key(|e| e.timestamp);
+    }
+    apply_pending_uses(&mut history);
+
+    // Rewrite the file once if any line was in an older format, so the
+    // migration doesn't run again on every load
+    let mut migrated = missing_favorites;
+    for entry in history.iter_mut() {
+        migrated |= migrate_entry(entry);
+    }
+    if migrated {
+        if let Err(e) = save_history(&history) {
+            log::error!("Failed to rewrite migrated history: {}", e);
+        }
+    }
+
+    history
+}
+
+/// Compiles the `mask_patterns` setting into one set
+fn build_mask_set(patterns: &[String]) -> Result<regex::RegexSet, String> {
+    regex::RegexSetBuilder::new(patterns)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid mask pattern: {}", e))
+}
+
+/// Hides the content of entries matching `mask_patterns` before they are
+/// returned to the UI
+fn mask_sensitive(entries: &mut [ClipboardEntry]) {
+    mask_entries(entries, active_mask_set().as_ref());
+}
+
+/// Hides the content of the entries matching `mask`, if any
+fn mask_entries<'a>(
+    entries: impl IntoIterator<Item = &'a mut ClipboardEntry>,
+    mask: Option<&regex::RegexSet>,
+) {
+    let Some(set) = mask else {
+        return;
+    };
+    for entry in entries.into_iter().filter(|e| set.is_match(&e.content)) {
+        entry.content = MASKED_CONTENT.to_string();
+        entry.html = None;
+        entry.masked = true;
+    }
+}
+
+/// The compiled `mask_patterns` setting, or `None` when nothing is masked
+fn active_mask_set() -> Option<regex::RegexSet> {
+    let patterns = current_settings().mask_patterns;
+    if patterns.is_empty() {
+        return None;
+    }
+    build_mask_set(&patterns)
+        .map_err(|e| log::error!("{}", e))
+        .ok()
+}
+
+/// The real content of entry `id`, for showing or copying a masked entry
+#[tauri::command]
+fn reveal_entry(id: String) -> Result<String, String> {
+    load_history()
+        .into_iter()
+        .find(|e| e.id == id)
+        .map(|e| e.content)
+        .ok_or_else(|| "Entry not found".to_string())
+}
+
 #[tauri::command]
 fn get_history() -> HistoryResponse {
     let mut history = load_history();
     history.reverse();
+    mask_sensitive(&mut history);
     HistoryResponse {
         entries: history,
         max_entries: MAX_HISTORY_ENTRIES,
     }
 }
 
-#[tauri::command]
-fn copy_to_clipboard(content: String) -> Result<(), String> {
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
-    clipboard.set_text(&content).map_err(|e| e.to_string())?;
-    Ok(())
+/// Searches the history for `query`.
+///
+/// Substring mode matches case-insensitively when every whitespace-separated
+/// term is contained in the content, newest first. Fuzzy mode ranks matches by
+/// `SkimMatcherV2` score, falling back to recency for equal scores.
+#[tauri::command]
+fn search_history(query: String, fuzzy: bool) -> Vec<SearchResult> {
+    let mut history = load_history();
+    history.reverse();
+    // Matching the masked form, so a query can't probe for hidden content
+    mask_sensitive(&mut history);
+    search_entries(history, &query, fuzzy)
+}
+
+fn search_entries(entries: Vec<ClipboardEntry>, query: &str, fuzzy: bool) -> Vec<SearchResult> {
+    if !fuzzy {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        return entries
+            .into_iter()
+            .filter(|e| {
+                let content = e.content.to_lowercase();
+                terms.iter().all(|term| content.contains(term.as_str()))
+            })
+            .map(|entry| SearchResult {
+                entry,
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let mut results: Vec<SearchResult> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (score, indices) = matcher.fuzzy_indices(&entry.content, query.trim())?;
+            Some(SearchResult {
+                entry,
+                score,
+                indices,
+            })
+        })
+        .collect();
+    // Stable sort keeps newest-first order among equal scores
+    results.sort_by_key(|r| std::cmp::Reverse(r.score));
+    results
+}
+
+/// Entries whose content matches the regular expression `pattern`, newest
+/// first. The `regex` crate runs in linear time, so user patterns can't
+/// backtrack catastrophically; the compiled size is capped as well.
+#[tauri::command]
+fn search_history_regex(pattern: String) -> Result<Vec<ClipboardEntry>, String> {
+    let regex = regex::RegexBuilder::new(&pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))?;
+
+    let mut matches: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| regex.is_match(&e.content))
+        .collect();
+    mask_sensitive(&mut matches);
+    Ok(matches)
+}
+
+/// Entries captured after `since` (an RFC 3339 timestamp), newest first, for
+/// incremental fetches. Empty when nothing is newer.
+#[tauri::command]
+fn get_history_since(since: DateTime<Local>) -> Vec<ClipboardEntry> {
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| e.timestamp > since)
+        .collect();
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Entries captured within the last `minutes` minutes, newest first
+#[tauri::command]
+fn get_recent(minutes: u32) -> Vec<ClipboardEntry> {
+    if minutes == 0 {
+        return Vec::new();
+    }
+    get_history_since(Local::now() - chrono::Duration::minutes(minutes.into()))
+}
+
+/// Entries with at least `min_chars` characters, newest first. Unlike the
+/// `min_entry_chars` setting this only filters the view; nothing is dropped.
+#[tauri::command]
+fn get_history_min_length(min_chars: usize) -> Vec<ClipboardEntry> {
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| e.content.chars().count() >= min_chars)
+        .collect();
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Entries of the given content kind, newest first
+#[tauri::command]
+fn get_history_by_kind(kind: ContentKind) -> Vec<ClipboardEntry> {
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| e.kind == kind)
+        .collect();
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Number of entries of each content kind, in `ContentKind` order. Kinds
+/// without entries are included with a count of 0.
+#[tauri::command]
+fn get_kind_summary() -> Vec<(ContentKind, usize)> {
+    let history = load_history();
+    ContentKind::ALL
+        .iter()
+        .map(|&kind| (kind, history.iter().filter(|e| e.kind == kind).count()))
+        .collect()
+}
+
+/// Checks the history file for unreadable lines, duplicates and entries past
+/// the history limit. With `repair`, rewrites the file without them: the
+/// newest of each duplicate is kept (pinned if any copy was), and the excess
+/// is evicted per `eviction_policy`.
+#[tauri::command]
+fn verify_history(repair: Option<bool>) -> Result<HistoryReport, String> {
+    let bytes = match fs::read(get_history_path()) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut entries = Vec::new();
+    let mut invalid_lines = 0;
+    for line in String::from_utf8_lossy(&bytes).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ClipboardEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => invalid_lines += 1,
+        }
+    }
+    let valid_lines = entries.len();
+
+    let settings = current_settings();
+    let mut cleaned: Vec<ClipboardEntry> = Vec::new();
+    let mut by_key: HashMap<(ContentKind, String), usize> = HashMap::new();
+    for entry in entries.into_iter().rev() {
+        let (kind, content) = dedup_key(&entry, &settings);
+        let key = (kind, content.into_owned());
+        match by_key.get(&key) {
+            Some(&i) => {
+                let kept = &mut cleaned[i];
+                kept.pinned |= entry.pinned;
+                kept.pinned_at = kept.pinned_at.or(entry.pinned_at);
+                kept.manual |= entry.manual;
+            }
+            None => {
+                by_key.insert(key, cleaned.len());
+                cleaned.push(entry);
+            }
+        }
+    }
+    cleaned.reverse();
+    let duplicate_entries = valid_lines - cleaned.len();
+    let over_cap = cleaned.len().saturating_sub(MAX_HISTORY_ENTRIES);
+
+    let repaired = repair.unwrap_or(false) && invalid_lines + duplicate_entries + over_cap > 0;
+    if repaired {
+        for entry in cleaned.iter_mut() {
+            migrate_entry(entry);
+        }
+        evict_unpinned(&mut cleaned, over_cap, settings.eviction_policy);
+        save_history(&cleaned).map_err(|e| e.to_string())?;
+    }
+
+    Ok(HistoryReport {
+        valid_lines,
+        invalid_lines,
+        duplicate_entries,
+        over_cap,
+        repaired,
+    })
+}
+
+/// The `n` entries with the largest content in bytes, largest first
+#[tauri::command]
+fn get_largest_entries(n: usize) -> Vec<ClipboardEntry> {
+    let mut entries = load_history();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.content.len()));
+    entries.truncate(n);
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Contents that appear in more than one entry, most frequent first.
+/// `save_entry` dedups new captures, so this mostly finds legacy duplicates.
+#[tauri::command]
+fn find_duplicates() -> Vec<DuplicateGroup> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for entry in load_history() {
+        match groups
+            .iter_mut()
+            .find(|(content, _)| *content == entry.content)
+        {
+            Some((_, ids)) => ids.push(entry.id),
+            None => groups.push((entry.content, vec![entry.id])),
+        }
+    }
+
+    let mask = active_mask_set();
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(content, ids)| DuplicateGroup {
+            preview: if mask.as_ref().is_some_and(|set| set.is_match(&content)) {
+                MASKED_CONTENT.to_string()
+            } else {
+                truncate_for_display(&content, 80)
+            },
+            count: ids.len(),
+            ids,
+        })
+        .collect();
+    duplicates.sort_by_key(|d| std::cmp::Reverse(d.count));
+    duplicates
+}
+
+/// Every distinct http/https URL found in the history, oldest first
+#[tauri::command]
+fn extract_urls() -> Vec<String> {
+    let mut history = load_history();
+    mask_sensitive(&mut history);
+    distinct_urls(&history)
+}
+
+fn distinct_urls(entries: &[ClipboardEntry]) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for entry in entries {
+        for url in find_urls(&entry.content) {
+            if !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// Line-based unified diff from the content of entry `id_a` to that of `id_b`.
+/// Empty when their lines are identical.
+#[tauri::command]
+fn diff_entries(id_a: String, id_b: String) -> Result<String, String> {
+    let mut history = load_history();
+    mask_sensitive(&mut history);
+    diff_contents(&history, &id_a, &id_b)
+}
+
+fn diff_contents(history: &[ClipboardEntry], id_a: &str, id_b: &str) -> Result<String, String> {
+    let find = |id: &str| {
+        history
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("Entry not found: {}", id))
+    };
+    let a = find(id_a)?;
+    let b = find(id_b)?;
+    Ok(TextDiff::from_lines(&a.content, &b.content)
+        .unified_diff()
+        .header(id_a, id_b)
+        .to_string())
+}
+
+/// Distinct source apps with their entry counts, sorted by name. Entries
+/// without a recorded source are counted under `UNKNOWN_SOURCE_APP`.
+#[tauri::command]
+fn get_source_apps() -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in load_history() {
+        let app = entry
+            .source_app
+            .unwrap_or_else(|| UNKNOWN_SOURCE_APP.to_string());
+        *counts.entry(app).or_default() += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// Entries whose source app matches `app` case-insensitively, newest first.
+/// An empty name or `UNKNOWN_SOURCE_APP` selects entries without a source.
+#[tauri::command]
+fn get_history_by_app(app: String) -> Vec<ClipboardEntry> {
+    let app = app.trim();
+    let want_unknown = app.is_empty() || app.eq_ignore_ascii_case(UNKNOWN_SOURCE_APP);
+    let app = app.to_lowercase();
+
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| match &e.source_app {
+            Some(source) => !want_unknown && source.to_lowercase() == app,
+            None => want_unknown,
+        })
+        .collect();
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Entries captured between `start` and `end` (inclusive), newest first
+#[tauri::command]
+fn get_history_in_range(
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> Result<Vec<ClipboardEntry>, String> {
+    if start > end {
+        return Err("Start of range must not be after its end".to_string());
+    }
+
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .filter(|e| e.timestamp >= start && e.timestamp <= end)
+        .collect();
+    mask_sensitive(&mut entries);
+    Ok(entries)
+}
+
+/// History as a Markdown document, newest first, optionally limited to the
+/// `limit` most recent entries
+#[tauri::command]
+fn export_markdown(limit: Option<usize>) -> String {
+    let mut entries: Vec<ClipboardEntry> = load_history()
+        .into_iter()
+        .rev()
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    mask_sensitive(&mut entries);
+    export::render_markdown(&entries)
+}
+
+/// Applies `transform` to `content`. Decoding fails with a descriptive error
+/// instead of producing garbage when the input isn't valid for the format.
+fn apply_transform(content: &str, transform: Transform) -> Result<String, String> {
+    use base64::Engine;
+    let engine = base64::engine::general_purpose::STANDARD;
+
+    match transform {
+        Transform::Base64Encode => Ok(engine.encode(content)),
+        Transform::Base64Decode => {
+            let bytes = engine
+                .decode(content.trim())
+                .map_err(|e| format!("Invalid base64: {}", e))?;
+            String::from_utf8(bytes)
+                .map_err(|_| "Decoded base64 is not valid UTF-8 text".to_string())
+        }
+        Transform::UrlEncode => Ok(urlencoding::encode(content).into_owned()),
+        Transform::UrlDecode => urlencoding::decode(content)
+            .map(|decoded| decoded.into_owned())
+            .map_err(|e| format!("Invalid URL encoding: {}", e)),
+        Transform::PrettyJson => pretty_json(content),
+    }
+}
+
+/// `content` as indented JSON, for viewing minified JSON entries. Errors if
+/// it isn't valid JSON.
+#[tauri::command]
+fn format_json(content: String) -> Result<String, String> {
+    pretty_json(&content)
+}
+
+/// Copies `content` to the clipboard. With `as_html`, the entry's stored HTML
+/// flavor is restored alongside the plain text; otherwise only plain text is set.
+/// Setting the clipboard replaces all of its flavors, so a plain copy drops any
+/// HTML the clipboard held before.
+///
+/// Unless `record` is set, the monitor skips the copied text instead of
+/// recording it again, so re-selecting an entry doesn't move it to the top.
+#[tauri::command]
+fn copy_to_clipboard(
+    content: String,
+    transform: Option<Transform>,
+    as_html: Option<bool>,
+    record: Option<bool>,
+) -> Result<(), String> {
+    let record = record.unwrap_or(false);
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+    if as_html.unwrap_or(false) && transform.is_none() {
+        let html = load_history()
+            .into_iter()
+            .rev()
+            .find(|e| e.content == content)
+            .and_then(|e| e.html);
+        if let Some(html) = html {
+            clipboard
+                .set_html(html.as_str(), Some(content.as_str()))
+                .map_err(|e| e.to_string())?;
+            if !record {
+                MONITOR.lock().unwrap().last_content = Some(content.clone());
+            }
+            mark_used(&content);
+            return Ok(());
+        }
+    }
+
+    let copied = match transform {
+        Some(transform) => apply_transform(&content, transform)?,
+        None => content.clone(),
+    };
+    clipboard.set_text(&copied).map_err(|e| e.to_string())?;
+    if !record {
+        MONITOR.lock().unwrap().last_content = Some(copied);
+    }
+    mark_used(&content);
+    Ok(())
+}
+
+/// Copies not yet written to the history file, as (content, time) pairs.
+/// `load_history` applies them, so eviction sees them right away.
+static PENDING_USES: Mutex<Vec<(String, DateTime<Local>)>> = Mutex::new(Vec::new());
+/// Pending copies that trigger a history rewrite to record them
+const PENDING_USES_FLUSH: usize = 20;
+
+/// Records that the entry with `content` was just copied, for LRU eviction.
+/// Only tracked under that policy, and written out in batches rather than
+/// rewriting the history on every copy.
+fn mark_used(content: &str) {
+    if current_settings().eviction_policy != EvictionPolicy::Lru {
+        return;
+    }
+    let mut uses = PENDING_USES.lock().unwrap();
+    uses.retain(|(c, _)| c != content);
+    uses.push((content.to_string(), Local::now()));
+    let flush = uses.len() >= PENDING_USES_FLUSH;
+    drop(uses);
+    if flush {
+        flush_pending_uses();
+    }
+}
+
+/// Writes the pending copy times to the history file
+fn flush_pending_uses() {
+    let flushed = PENDING_USES.lock().unwrap().clone();
+    if flushed.is_empty() {
+        return;
+    }
+    // Loading applies the pending uses; once saved they are recorded. Uses
+    // added meanwhile stay pending.
+    let history = load_history();
+    match save_history(&history) {
+        Ok(()) => PENDING_USES
+            .lock()
+            .unwrap()
+            .retain(|used| !flushed.contains(used)),
+        Err(e) => log::error!("Failed to record entry use: {}", e),
+    }
+}
+
+fn apply_pending_uses(history: &mut [ClipboardEntry]) {
+    for (content, used) in PENDING_USES.lock().unwrap().iter() {
+        if let Some(entry) = history.iter_mut().rev().find(|e| &e.content == content) {
+            entry.last_used = Some(*used);
+        }
+    }
+}
+
+/// Copies the contents of the entries `ids`, in the given order, joined with
+/// `separator`. The joined text is new content, so the monitor records it.
+#[tauri::command]
+fn copy_multiple(ids: Vec<String>, separator: String) -> Result<(), String> {
+    let history = load_history();
+    let contents = ids
+        .iter()
+        .map(|id| {
+            history
+                .iter()
+                .find(|e| &e.id == id)
+                .map(|e| e.content.as_str())
+                .ok_or_else(|| format!("Entry not found: {}", id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard
+        .set_text(contents.join(&separator))
+        .map_err(|e| e.to_string())
+}
+
+/// Copies only the plain text of `content`, for "paste and match style"
+#[tauri::command]
+fn copy_plain(content: String) -> Result<(), String> {
+    copy_to_clipboard(content, None, None, None)
+}
+
+/// Sends Cmd+V to the frontmost application
+#[cfg(target_os = "macos")]
+fn simulate_paste() {
+    use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) else {
+        return;
+    };
+    for key_down in [true, false] {
+        if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), KEY_CODE_V, key_down) {
+            event.set_flags(CGEventFlags::CGEventFlagCommand);
+            event.post(CGEventTapLocation::HID);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn simulate_paste() {
+    // No-op on non-macOS platforms
+}
+
+/// Types `text` into the frontmost application one key event per character,
+/// for apps that reject pasting. Line breaks and tabs are sent as the Return
+/// and Tab keys; everything else as the character itself.
+#[cfg(target_os = "macos")]
+fn type_text(text: &str) {
+    use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let text = normalize_line_endings(text);
+    let chars = text.chars().count();
+    if chars > 1000 {
+        let seconds = (TYPING_KEY_INTERVAL * chars as u32).as_secs();
+        log::warn!("Typing {} characters takes at least {}s", chars, seconds);
+    }
+
+    let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) else {
+        return;
+    };
+    let mut buf = [0; 4];
+    for c in text.chars() {
+        let (key_code, string) = match c {
+            '\n' => (KEY_CODE_RETURN, None),
+            '\t' => (KEY_CODE_TAB, None),
+            // The key code is irrelevant once the event carries a string
+            _ => (0, Some(c.encode_utf8(&mut buf) as &str)),
+        };
+        for key_down in [true, false] {
+            if let Ok(event) = CGEvent::new_keyboard_event(source.clone(), key_code, key_down) {
+                // Ignore modifiers still held from the shortcut that triggered this
+                event.set_flags(CGEventFlags::CGEventFlagNull);
+                if let Some(string) = string {
+                    event.set_string(string);
+                }
+                event.post(CGEventTapLocation::HID);
+            }
+        }
+        thread::sleep(TYPING_KEY_INTERVAL);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn type_text(_text: &str) {
+    // No-op on non-macOS platforms
+}
+
+/// Copies the `index`-th most recent entry and pastes it into the frontmost
+/// app. Does nothing when the history has fewer entries.
+fn paste_slot(index: usize) {
+    copy_recent(index, true);
+}
+
+/// Copies the entry `copy_nth_offset` places before the newest one (the
+/// previous copy by default), pasting it as well when `copy_nth_paste` is set
+fn copy_nth_entry() {
+    let settings = current_settings();
+    copy_recent(settings.copy_nth_offset, settings.copy_nth_paste);
+}
+
+/// Copies the `index`-th most recent entry, optionally pasting it into the
+/// frontmost app. Does nothing when the history has fewer entries.
+fn copy_recent(index: usize, paste: bool) {
+    let Some(entry) = load_history().into_iter().rev().nth(index) else {
+        return;
+    };
+
+    if let Err(e) = copy_to_clipboard(entry.content.clone(), None, None, None) {
+        log::error!("Failed to copy entry {}: {}", index + 1, e);
+        return;
+    }
+    if paste {
+        if current_settings().paste_as_typing {
+            // Typing a long entry takes a while; don't hold up the thread
+            // that delivers the shortcuts meanwhile
+            thread::spawn(move || {
+                let _typing = TYPING.lock().unwrap();
+                type_text(&entry.content);
+            });
+        } else {
+            simulate_paste();
+        }
+    }
+}
+
+/// Loads the last `n` entries into the paste queue in the order they were
+/// copied (oldest first) and returns them
+#[tauri::command]
+fn get_paste_queue(n: usize) -> Vec<ClipboardEntry> {
+    let history = load_history();
+    let start = history.len().saturating_sub(n);
+    let mut entries: Vec<ClipboardEntry> = history[start..].to_vec();
+
+    // The queue keeps the real content to copy; only the returned copy is masked
+    let mut queue = PASTE_QUEUE.lock().unwrap();
+    queue.entries = entries.iter().cloned().collect();
+    queue.last_copied = None;
+    mask_sensitive(&mut entries);
+    entries
+}
+
+/// Copies the front of the paste queue to the clipboard and removes it.
+/// Returns `None` once the queue is exhausted.
+#[tauri::command]
+fn next_in_queue() -> Result<Option<ClipboardEntry>, String> {
+    let mut queue = PASTE_QUEUE.lock().unwrap();
+    let Some(mut entry) = queue.entries.pop_front() else {
+        queue.last_copied = None;
+        return Ok(None);
+    };
+
+    copy_to_clipboard(entry.content.clone(), None, None, None)?;
+    queue.last_copied = Some(entry.content.clone());
+    mask_sensitive(std::slice::from_mut(&mut entry));
+    Ok(Some(entry))
+}
+
+/// Drops the paste queue when genuinely new content is captured
+fn reset_paste_queue_on_capture(content: &str) {
+    let mut queue = PASTE_QUEUE.lock().unwrap();
+    if queue.last_copied.as_deref() != Some(content) {
+        queue.entries.clear();
+        queue.last_copied = None;
+    }
+}
+
+/// Records typed text as a history entry and puts it on the clipboard
+#[tauri::command]
+fn add_manual_entry(app_handle: AppHandle, content: String) -> Result<(), String> {
+    if content.trim().is_empty() {
+        return Err("Content is empty".to_string());
+    }
+
+    let mut entry = ClipboardEntry::new(content);
+    entry.manual = true;
+    save_entry(&entry).map_err(|e| e.to_string())?;
+    copy_to_clipboard(entry.content.clone(), None, None, None)?;
+
+    mask_sensitive(std::slice::from_mut(&mut entry));
+    let _ = app_handle.emit("clipboard-changed", &entry);
+    Ok(())
+}
+
+#[tauri::command]
+fn toggle_pin(timestamp: String, pinned: bool) -> Result<(), String> {
+    let history = load_history();
+
+    // Find the entry by timestamp and update its pinned state. Compared as
+    // instants, since the UI's copy may carry a different offset.
+    let timestamp = DateTime::parse_from_rfc3339(&timestamp).map_err(|e| e.to_string())?;
+    let Some(index) = history.iter().position(|e| e.timestamp == timestamp) else {
+        return Err("Entry not found".to_string());
+    };
+
+    set_pinned(history, index, pinned)?;
+    Ok(())
+}
+
+/// Pins or unpins the entry whose content is exactly `content`, returning
+/// whether anything changed. Pinning content that isn't in the history yet
+/// records it as a new pinned entry.
+#[tauri::command]
+fn pin_by_content(app_handle: AppHandle, content: String, pinned: bool) -> Result<bool, String> {
+    let mut history = load_history();
+    let mut index = history.iter().rposition(|e| e.content == content);
+
+    if index.is_none() && pinned {
+        if content.trim().is_empty() {
+            return Err("Content is empty".to_string());
+        }
+        let mut entry = ClipboardEntry::new(content);
+        entry.manual = true;
+        save_entry(&entry).map_err(|e| e.to_string())?;
+        history = load_history();
+        // Looked up by content: a dedup may have kept an existing entry's id
+        index = history.iter().rposition(|e| e.content == entry.content);
+    }
+
+    let Some(index) = index else {
+        return Ok(false);
+    };
+    let changed = set_pinned(history, index, pinned)?;
+    if changed {
+        let _ = app_handle.emit("history-updated", ());
+    }
+    Ok(changed)
+}
+
+/// Sets the pinned state of `history[index]` and persists the history and
+/// favorites. Returns whether the state changed.
+fn set_pinned(
+    mut history: Vec<ClipboardEntry>,
+    index: usize,
+    pinned: bool,
+) -> Result<bool, String> {
+    if history[index].pinned == pinned {
+        return Ok(false);
+    }
+    if pinned {
+        make_room_for_pin(&mut history, &current_settings())?;
+    }
+    history[index].pinned_at = pinned.then(Local::now);
+    history[index].pinned = pinned;
+
+    save_history(&history).map_err(|e| e.to_string())?;
+    save_favorites(&history).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Enforces `max_pinned` before another entry is pinned: either unpins the
+/// oldest pinned entry or refuses, depending on `unpin_oldest_when_full`.
+fn make_room_for_pin(history: &mut [ClipboardEntry], settings: &Settings) -> Result<(), String> {
+    let pinned_count = history.iter().filter(|e| e.pinned).count();
+    if settings.max_pinned == 0 || pinned_count < settings.max_pinned {
+        return Ok(());
+    }
+
+    if !settings.unpin_oldest_when_full {
+        return Err(format!(
+            "Cannot pin more than {} entries",
+            settings.max_pinned
+        ));
+    }
+
+    if let Some(oldest) = history
+        .iter_mut()
+        .filter(|e| e.pinned)
+        .min_by_key(|e| pinned_time(e))
+    {
+        oldest.pinned = false;
+        oldest.pinned_at = None;
+    }
+    Ok(())
+}
+
+/// When `entry` was pinned, falling back to its capture time for entries
+/// pinned before `pinned_at` was recorded
+fn pinned_time(entry: &ClipboardEntry) -> DateTime<Local> {
+    entry.pinned_at.unwrap_or(entry.timestamp)
+}
+
+/// Pinned entries only, most recently pinned first
+#[tauri::command]
+fn get_pinned() -> Vec<ClipboardEntry> {
+    let mut pinned: Vec<ClipboardEntry> = load_history().into_iter().filter(|e| e.pinned).collect();
+    pinned.sort_by_key(|e| std::cmp::Reverse(pinned_time(e)));
+    mask_sensitive(&mut pinned);
+    pinned
+}
+
+/// Replaces the undo buffer with `removed` and tells the UI it can offer undo
+fn remember_removal(app_handle: &AppHandle, removed: Vec<ClipboardEntry>) {
+    if removed.is_empty() {
+        return;
+    }
+    let count = removed.len();
+    *UNDO_BUFFER.lock().unwrap() = removed;
+    let _ = app_handle.emit("undo-available", count);
+}
+
+/// Puts previously removed entries back in chronological order, skipping any
+/// whose id or content is already present. Returns the number restored.
+fn restore_entries(entries: Vec<ClipboardEntry>) -> std::io::Result<usize> {
+    let mut history = load_history();
+    let before = history.len();
+    let mut restored_pinned = false;
+
+    for entry in entries {
+        if !history
+            .iter()
+            .any(|e| e.id == entry.id || e.content == entry.content)
+        {
+            restored_pinned |= entry.pinned;
+            history.push(entry);
+        }
+    }
+
+    let restored = history.len() - before;
+    history.sort_by_key(|e| e.timestamp);
+    save_history(&history)?;
+    if restored_pinned {
+        save_favorites(&history)?;
+    }
+    Ok(restored)
+}
+
+#[tauri::command]
+fn delete_entry(app_handle: AppHandle, id: String) -> Result<(), String> {
+    let mut history = load_history();
+    let index = history
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| "Entry not found".to_string())?;
+    let removed = history.remove(index);
+
+    save_history(&history).map_err(|e| e.to_string())?;
+    // Deleting a pinned entry by hand is an explicit request to drop it
+    if removed.pinned {
+        save_favorites(&history).map_err(|e| e.to_string())?;
+    }
+    trash::move_to_trash(vec![removed.clone()]).map_err(|e| e.to_string())?;
+    remember_removal(&app_handle, vec![removed]);
+    Ok(())
+}
+
+/// Sets the title shown for entry `id` in place of its content. An empty or
+/// missing label shows the content again.
+#[tauri::command]
+fn set_label(app_handle: AppHandle, id: String, label: Option<String>) -> Result<(), String> {
+    update_entry(&app_handle, &id, |entry| entry.label = non_blank(label))
+}
+
+/// Attaches a note to entry `id`; an empty or missing note removes it
+#[tauri::command]
+fn set_note(app_handle: AppHandle, id: String, note: Option<String>) -> Result<(), String> {
+    update_entry(&app_handle, &id, |entry| entry.note = non_blank(note))
+}
+
+/// `text` trimmed, or `None` when that leaves nothing
+fn non_blank(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Applies `change` to entry `id` in place and saves the history (and the
+/// favorites, for a pinned entry)
+fn update_entry(
+    app_handle: &AppHandle,
+    id: &str,
+    change: impl FnOnce(&mut ClipboardEntry),
+) -> Result<(), String> {
+    let mut history = load_history();
+    let entry = history
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| "Entry not found".to_string())?;
+    change(entry);
+    let pinned = entry.pinned;
+
+    save_history(&history).map_err(|e| e.to_string())?;
+    if pinned {
+        save_favorites(&history).map_err(|e| e.to_string())?;
+    }
+    let _ = app_handle.emit("history-updated", ());
+    Ok(())
+}
+
+/// Moves an entry to the newest position without going through the clipboard
+#[tauri::command]
+fn bump_entry(app_handle: AppHandle, id: String) -> Result<(), String> {
+    let mut history = load_history();
+    let index = history
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| "Entry not found".to_string())?;
+
+    let mut entry = history.remove(index);
+    entry.timestamp = Local::now();
+    history.push(entry);
+
+    save_history(&history).map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("history-updated", ());
+    Ok(())
+}
+
+#[tauri::command]
+fn undo_last_removal() -> Result<usize, String> {
+    let removed = std::mem::take(&mut *UNDO_BUFFER.lock().unwrap());
+    if removed.is_empty() {
+        return Ok(0);
+    }
+    // Undone deletes shouldn't also linger in the trash
+    let ids: Vec<String> = removed.iter().map(|e| e.id.clone()).collect();
+    trash::take_from_trash(&ids).map_err(|e| e.to_string())?;
+    restore_entries(removed).map_err(|e| e.to_string())
+}
+
+/// Deleted entries still within the retention period, most recently deleted first
+#[tauri::command]
+fn get_trash() -> Vec<TrashedEntry> {
+    let mut trash = trash::load_trash();
+    trash.reverse();
+    mask_entries(
+        trash.iter_mut().map(|t| &mut t.entry),
+        active_mask_set().as_ref(),
+    );
+    trash
+}
+
+#[tauri::command]
+fn restore_from_trash(app_handle: AppHandle, id: String) -> Result<(), String> {
+    let entries = trash::take_from_trash(&[id]).map_err(|e| e.to_string())?;
+    if entries.is_empty() {
+        return Err("Entry not found in trash".to_string());
+    }
+    restore_entries(entries).map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("history-updated", ());
+    Ok(())
+}
+
+/// Keeps the newest `keep` unpinned entries plus every pinned entry and
+/// returns how many were removed. `keep == 0` leaves only pinned entries.
+#[tauri::command]
+fn trim_history(app_handle: AppHandle, keep: usize) -> Result<usize, String> {
+    let history = load_history();
+    let unpinned_count = history.iter().filter(|e| !e.pinned).count();
+    let mut to_remove = unpinned_count.saturating_sub(keep);
+    if to_remove == 0 {
+        return Ok(0);
+    }
+
+    // History is oldest first, so the first unpinned entries go
+    let (kept, removed): (Vec<_>, Vec<_>) = history.into_iter().partition(|e| {
+        if e.pinned || to_remove == 0 {
+            true
+        } else {
+            to_remove -= 1;
+            false
+        }
+    });
+
+    save_history(&kept).map_err(|e| e.to_string())?;
+    let count = removed.len();
+    remember_removal(&app_handle, removed);
+    let _ = app_handle.emit("history-updated", ());
+    Ok(count)
+}
+
+/// Removes every unpinned entry of the given content kind, e.g. all URLs,
+/// and returns how many were removed
+#[tauri::command]
+fn clear_by_kind(app_handle: AppHandle, kind: ContentKind) -> Result<usize, String> {
+    let history = load_history();
+    let (kept, removed): (Vec<_>, Vec<_>) = history
+        .into_iter()
+        .partition(|e| e.pinned || e.kind != kind);
+    if removed.is_empty() {
+        return Ok(0);
+    }
+
+    save_history(&kept).map_err(|e| e.to_string())?;
+    let count = removed.len();
+    remember_removal(&app_handle, removed);
+    let _ = app_handle.emit("history-updated", ());
+    Ok(count)
+}
+
+#[tauri::command]
+fn clear_all_history(app_handle: AppHandle) -> Result<(), String> {
+    let history = load_history();
+    let (pinned, removed): (Vec<_>, Vec<_>) = history.into_iter().partition(|e| e.pinned);
+
+    if pinned.is_empty() {
+        let _data = DATA_LOCK.lock().unwrap();
+        let path = get_history_path();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+    } else {
+        save_history(&pinned).map_err(|e| e.to_string())?;
+    }
+    remember_removal(&app_handle, removed);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_stats() -> HistoryStats {
+    let history = load_history();
+    let file_bytes = fs::metadata(get_history_path())
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    HistoryStats {
+        total_entries: history.len(),
+        pinned_entries: history.iter().filter(|e| e.pinned).count(),
+        content_bytes: history.iter().map(|e| e.content.len()).sum(),
+        file_bytes,
+        oldest: history.iter().map(|e| e.timestamp).min(),
+        newest: history.iter().map(|e| e.timestamp).max(),
+    }
+}
+
+/// Location of the active profile's history file, for display
+#[tauri::command]
+fn get_history_file_path() -> String {
+    get_history_path().to_string_lossy().into_owned()
+}
+
+/// Location of the data directory (history, settings and logs), for display
+#[tauri::command]
+fn get_data_dir_path() -> String {
+    get_data_dir().to_string_lossy().into_owned()
+}
+
+/// Moves all data (history, favorites, trash, settings and logs) to
+/// `new_dir`, which must be empty or not exist yet.
+///
+/// Every file is copied and compared before the new location is made active,
+/// and a failure removes the partial copy, so the data is never split between
+/// the two directories. With `remove_old`, the old files are deleted once the
+/// switch is done.
+#[tauri::command]
+fn migrate_data_dir(new_dir: PathBuf, remove_old: Option<bool>) -> Result<(), String> {
+    let data = DATA_LOCK.lock().unwrap();
+    let old_dir = get_data_dir();
+    if !new_dir.is_absolute() {
+        return Err("The new data directory must be an absolute path".to_string());
+    }
+    if new_dir == old_dir {
+        return Ok(());
+    }
+    if new_dir.starts_with(&old_dir) {
+        return Err("The new data directory can't be inside the current one".to_string());
+    }
+    if !is_unused_dir(&new_dir, new_dir == default_data_dir()) {
+        return Err(format!("{} is not empty", new_dir.display()));
+    }
+
+    let mut files = Vec::new();
+    list_data_files(&old_dir, Path::new(""), &mut files).map_err(|e| e.to_string())?;
+    let switched = copy_verified(&old_dir, &new_dir, &files)
+        .and_then(|()| point_data_dir_to(&new_dir))
+        .map_err(|e| e.to_string());
+    if let Err(e) = switched {
+        remove_copies(&new_dir, &files);
+        return Err(format!("Failed to move the data directory: {}", e));
+    }
+    *DATA_DIR.lock().unwrap() = Some(new_dir);
+    drop(data);
+
+    // Writes go to the new directory from here on
+    if remove_old.unwrap_or(false) {
+        for file in &files {
+            if let Err(e) = fs::remove_file(old_dir.join(file)) {
+                log::error!("Failed to remove {}: {}", file.display(), e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `dir` is missing or empty. The default directory keeps the
+/// pointer file while the data lives elsewhere, so that file doesn't count
+/// there.
+fn is_unused_dir(dir: &Path, is_default: bool) -> bool {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .all(|entry| is_default && entry.file_name() == DATA_DIR_POINTER),
+        Err(_) => true,
+    }
+}
+
+/// Undoes a partial `copy_verified` into `dir`: removes the copied files,
+/// then any directories left empty, including `dir` itself
+fn remove_copies(dir: &Path, files: &[PathBuf]) {
+    for file in files {
+        let _ = fs::remove_file(dir.join(file));
+    }
+    for file in files {
+        for parent in file.ancestors().skip(1) {
+            let _ = fs::remove_dir(dir.join(parent));
+        }
+    }
+}
+
+/// Paths, relative to `dir`, of every file under `dir` except the pointer file
+fn list_data_files(dir: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            list_data_files(dir, &path, files)?;
+        } else if path != Path::new(DATA_DIR_POINTER) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Copies `files` from `from` to `to` and checks each copy matches
+fn copy_verified(from: &Path, to: &Path, files: &[PathBuf]) -> std::io::Result<()> {
+    for file in files {
+        let dest = to.join(file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from.join(file), &dest)?;
+        if fs::read(from.join(file))? != fs::read(&dest)? {
+            return Err(std::io::Error::other(format!(
+                "{} changed while copying",
+                file.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Makes `dir` the data directory on the next launch too. The pointer file is
+/// replaced atomically, so it never holds a partial path.
+fn point_data_dir_to(dir: &Path) -> std::io::Result<()> {
+    let default = default_data_dir();
+    let pointer = default.join(DATA_DIR_POINTER);
+    if dir == default {
+        return match fs::remove_file(&pointer) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    fs::create_dir_all(&default)?;
+    let temp = default.join(format!("{}.tmp", DATA_DIR_POINTER));
+    fs::write(&temp, dir.to_string_lossy().as_bytes())?;
+    fs::rename(&temp, &pointer)
+}
+
+/// Reveals the data directory (history, settings and logs) in the file manager
+#[tauri::command]
+fn open_data_dir(app_handle: AppHandle) -> Result<(), String> {
+    let dir = get_data_dir();
+    // `open` is deprecated in favor of tauri-plugin-opener, which isn't a dependency
+    #[allow(deprecated)]
+    app_handle
+        .shell()
+        .open(dir.to_string_lossy(), None)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_settings() -> Settings {
+    current_settings()
+}
+
+#[tauri::command]
+fn update_settings(app_handle: AppHandle, settings: Settings) -> Result<(), String> {
+    validate_settings(&settings)?;
+    apply_window_settings(&app_handle, &settings);
+    save_settings(settings).map_err(|e| e.to_string())
+}
+
+/// Rejects settings the app couldn't run with, whether they come from the UI
+/// or a restored backup
+fn validate_settings(settings: &Settings) -> Result<(), String> {
+    if !is_valid_profile_name(&settings.profile) {
+        return Err(INVALID_PROFILE_NAME.to_string());
+    }
+    build_mask_set(&settings.mask_patterns)?;
+    if settings.toggle_monitoring_enabled {
+        Shortcut::parse(&settings.toggle_monitoring_shortcut)?;
+    }
+    if let Some(size) = settings.popup_font_size {
+        if !POPUP_FONT_SIZES.contains(&size) {
+            return Err(format!(
+                "Font size must be between {} and {}",
+                POPUP_FONT_SIZES.start(),
+                POPUP_FONT_SIZES.end()
+            ));
+        }
+    }
+    // The family ends up in a CSS declaration
+    if let Some(family) = &settings.popup_font_family {
+        if family.contains([';', '{', '}']) {
+            return Err("Invalid font family".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Writes history and settings to a single JSON archive at `dest`
+#[tauri::command]
+fn backup(dest: PathBuf) -> Result<BackupSummary, String> {
+    let archive = Backup::new(current_settings(), load_history());
+    let json = serde_json::to_string_pretty(&archive).map_err(|e| e.to_string())?;
+    fs::write(&dest, json).map_err(|e| e.to_string())?;
+    Ok(archive.summary())
+}
+
+/// Replaces settings and history with the contents of the archive at `src`.
+/// The whole archive is validated, and every file written under a temporary
+/// name, before anything is overwritten.
+#[tauri::command]
+fn restore(app_handle: AppHandle, src: PathBuf) -> Result<BackupSummary, String> {
+    let json = fs::read_to_string(&src).map_err(|e| e.to_string())?;
+    let archive = backup::parse_backup(&json)?;
+    let summary = archive.summary();
+    let settings_json =
+        serde_json::to_string_pretty(&archive.settings).map_err(|e| e.to_string())?;
+    let pinned: Vec<ClipboardEntry> = archive
+        .history
+        .iter()
+        .filter(|e| e.pinned)
+        .cloned()
+        .collect();
+
+    {
+        let _data = DATA_LOCK.lock().unwrap();
+        // The history goes to the restored profile's files
+        let profile = &archive.settings.profile;
+        let targets = [
+            settings::get_settings_path(),
+            profile_file("clipboard_history", profile),
+            profile_file("favorites", profile),
+        ];
+        let temps = targets.clone().map(|path| {
+            let mut name = path.into_os_string();
+            name.push(".tmp");
+            PathBuf::from(name)
+        });
+
+        let written = fs::write(&temps[0], settings_json)
+            .and_then(|()| write_entries(&temps[1], &archive.history))
+            .and_then(|()| write_entries(&temps[2], &pinned));
+        if let Err(e) = written {
+            for temp in &temps {
+                let _ = fs::remove_file(temp);
+            }
+            return Err(format!("Failed to restore: {}", e));
+        }
+        for (temp, target) in temps.iter().zip(&targets) {
+            fs::rename(temp, target).map_err(|e| format!("Failed to restore: {}", e))?;
+            remember_own_write(target);
+        }
+    }
+
+    settings::set_current_settings(archive.settings.clone());
+    apply_window_settings(&app_handle, &archive.settings);
+    UNDO_BUFFER.lock().unwrap().clear();
+    let _ = app_handle.emit("settings-updated", &archive.settings);
+    let _ = app_handle.emit("history-updated", ());
+    Ok(summary)
+}
+
+#[tauri::command]
+fn switch_profile(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if !is_valid_profile_name(&name) {
+        return Err(INVALID_PROFILE_NAME.to_string());
+    }
+
+    let mut settings = current_settings();
+    settings.profile = name;
+    save_settings(settings).map_err(|e| e.to_string())?;
+
+    // Undo must not restore entries into a different profile
+    UNDO_BUFFER.lock().unwrap().clear();
+    let _ = app_handle.emit("history-updated", ());
+    Ok(())
+}
+
+#[tauri::command]
+fn toggle_always_on_top(app_handle: AppHandle) -> Result<bool, String> {
+    let mut settings = current_settings();
+    settings.always_on_top = !settings.always_on_top;
+    let enabled = settings.always_on_top;
+
+    apply_window_settings(&app_handle, &settings);
+    save_settings(settings).map_err(|e| e.to_string())?;
+    Ok(enabled)
+}
+
+/// How often the settings and history files are checked for outside changes
+const FILE_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Re-reads settings and history from disk, picking up hand edits and
+/// changes made by another instance
+#[tauri::command]
+fn reload(app_handle: AppHandle) -> Result<(), String> {
+    reload_settings_into(&app_handle)?;
+    let _ = app_handle.emit("history-updated", ());
+    Ok(())
+}
+
+/// Makes `settings.json` the active settings and tells the UI about them. The
+/// monitor reads the settings on every poll, so it needs no restart.
+fn reload_settings_into(app_handle: &AppHandle) -> Result<(), String> {
+    let settings = settings::reload_settings()?;
+    apply_window_settings(app_handle, &settings);
+    let _ = app_handle.emit("settings-updated", &settings);
+    Ok(())
+}
+
+/// Polls the modification times of the settings and history files and
+/// reloads whichever changed. The app's own writes are skipped; a reload
+/// would reset the UI's selection after every copy.
+fn start_file_watcher(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut settings_modified = modified(&settings::get_settings_path());
+        let mut history_modified = modified(&get_history_path());
+
+        loop {
+            thread::sleep(FILE_WATCH_INTERVAL);
+
+            let path = settings::get_settings_path();
+            let current = modified(&path);
+            if current != settings_modified {
+                settings_modified = current;
+                if !is_own_write(&path, current) {
+                    if let Err(e) = reload_settings_into(&app_handle) {
+                        log::error!("Failed to reload settings: {}", e);
+                    }
+                }
+            }
+
+            // Read after the settings, since the profile picks the history file
+            let path = get_history_path();
+            let current = modified(&path);
+            if current != history_modified {
+                history_modified = current;
+                if !is_own_write(&path, current) {
+                    let _ = app_handle.emit("history-updated", ());
+                }
+            }
+        }
+    });
+}
+
+/// Shows the main window in the middle of the screen, for launches that
+/// don't come from the hotkey
+fn show_window_centered(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.center();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Applies the settings that map directly onto main window properties
+fn apply_window_settings(app_handle: &AppHandle, settings: &Settings) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.set_always_on_top(settings.always_on_top);
+
+        if settings.window_width.is_some() || settings.window_height.is_some() {
+            let scale_factor = window.scale_factor().unwrap_or(1.0);
+            if let Ok(size) = window.inner_size() {
+                let size = size.to_logical::<f64>(scale_factor);
+                let width = settings
+                    .window_width
+                    .map_or(size.width, |w| w.max(MIN_WINDOW_WIDTH) as f64);
+                let height = settings
+                    .window_height
+                    .map_or(size.height, |h| h.max(MIN_WINDOW_HEIGHT) as f64);
+                let _ = window.set_size(LogicalSize::new(width, height));
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[tauri::command]
+fn restore_previous_app() -> Result<(), String> {
+    let previous_app = PREVIOUS_APP.lock().unwrap().clone();
+    if let Some(app) = previous_app {
+        // Use empty options set - the default behavior will activate the app
+        app.activateWithOptions(objc2_app_kit::NSApplicationActivationOptions::empty());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+#[tauri::command]
+fn restore_previous_app() -> Result<(), String> {
+    Ok(())
+}
+
+/// Shows a desktop notification for a new capture, at most once per
+/// `CAPTURE_NOTIFICATION_INTERVAL`
+fn notify_capture(app_handle: &AppHandle, entry: &ClipboardEntry) {
+    use tauri_plugin_notification::NotificationExt;
+
+    static LAST_NOTIFICATION: Mutex<Option<Instant>> = Mutex::new(None);
+    let mut last = LAST_NOTIFICATION.lock().unwrap();
+    if last.is_some_and(|t| t.elapsed() < CAPTURE_NOTIFICATION_INTERVAL) {
+        return;
+    }
+    *last = Some(Instant::now());
+
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title("Recall")
+        .body(truncate_for_display(&entry.content, 80))
+        .show()
+    {
+        log::error!("Failed to show capture notification: {}", e);
+    }
+}
+
+/// Created in the data directory on the first launch
+const FIRST_RUN_MARKER: &str = "initialized";
+
+/// Whether this is the first launch, i.e. the first-run marker doesn't exist
+/// yet. Creates the marker. Installs from before the marker are recognized by
+/// their history file, which can't be relied on alone since clearing the
+/// history or switching profiles leaves no file.
+fn is_first_run() -> bool {
+    let marker = get_data_dir().join(FIRST_RUN_MARKER);
+    if marker.exists() {
+        return false;
+    }
+    if let Err(e) = fs::write(&marker, "") {
+        log::error!("Failed to create first-run marker: {}", e);
+    }
+    !get_history_path().exists()
+}
+
+/// Greets a new user: records what's already on the clipboard, unless
+/// `first_run_capture` is off, and says how to open the window since nothing
+/// is shown at launch
+fn handle_first_run(app_handle: &AppHandle) {
+    use tauri_plugin_notification::NotificationExt;
+
+    if current_settings().first_run_capture {
+        if let Err(e) = capture_now(app_handle.clone()) {
+            log::error!("Failed to capture clipboard on first run: {}", e);
+        }
+    } else if let Ok(mut clipboard) = Clipboard::new() {
+        // Otherwise the monitor would record it on its first poll
+        MONITOR.lock().unwrap().last_content = clipboard.get_text().ok();
+    }
+
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title("Recall")
+        .body("Double-tap the Option key to open your clipboard history")
+        .show()
+    {
+        log::error!("Failed to show welcome notification: {}", e);
+    }
+}
+
+/// Pauses or resumes monitoring from the global shortcut, and says which
+/// with a notification since there's no window to show it in
+fn toggle_monitoring(app_handle: &AppHandle) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let enabled = !is_monitoring_enabled();
+    set_monitoring_enabled(app_handle.clone(), enabled);
+    let _ = app_handle.emit("monitoring-changed", enabled);
+
+    let body = if enabled {
+        "Clipboard monitoring resumed"
+    } else {
+        "Clipboard monitoring paused"
+    };
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title("Recall")
+        .body(body)
+        .show()
+    {
+        log::error!("Failed to show monitoring notification: {}", e);
+    }
+}
+
+#[tauri::command]
+fn set_monitoring_enabled(app_handle: AppHandle, enabled: bool) {
+    if enabled {
+        start_clipboard_monitor(app_handle);
+    } else {
+        // The monitor thread notices on its next poll and exits
+        MONITOR.lock().unwrap().enabled = false;
+    }
+}
+
+#[tauri::command]
+fn is_monitoring_enabled() -> bool {
+    MONITOR.lock().unwrap().enabled
+}
+
+/// Why the clipboard monitor last failed to capture, if it hasn't succeeded
+/// since; lets the UI explain why nothing is being recorded
+#[tauri::command]
+fn get_last_monitor_error() -> Option<String> {
+    MONITOR.lock().unwrap().last_error.clone()
+}
+
+/// Keeps `error` for `get_last_monitor_error`. It is only logged when it
+/// differs from the previous error, as a failing read repeats on every poll.
+fn set_monitor_error(error: String) {
+    let mut monitor = MONITOR.lock().unwrap();
+    if monitor.last_error.as_ref() != Some(&error) {
+        log::error!("{}", error);
+    }
+    monitor.last_error = Some(error);
+}
+
+/// Logs a failed capture and keeps it for `get_last_monitor_error`; a
+/// successful one clears the previous error
+fn note_capture_result(result: std::io::Result<Option<ClipboardEntry>>) -> Option<ClipboardEntry> {
+    match result {
+        Ok(entry) => {
+            if entry.is_some() {
+                MONITOR.lock().unwrap().last_error = None;
+            }
+            entry
+        }
+        Err(e) => {
+            log::error!("保存エラー: {}", e);
+            MONITOR.lock().unwrap().last_error = Some(format!("Failed to save the history: {}", e));
+            None
+        }
+    }
+}
+
+/// Enables capture and starts the monitor thread unless it is still running
+fn start_clipboard_monitor(app_handle: AppHandle) {
+    let mut monitor = MONITOR.lock().unwrap();
+    monitor.enabled = true;
+    if monitor.thread_alive {
+        return;
+    }
+    monitor.thread_alive = true;
+
+    thread::spawn(move || {
+        let mut clipboard = match Clipboard::new() {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to access clipboard: {}", e);
+                let mut monitor = MONITOR.lock().unwrap();
+                monitor.thread_alive = false;
+                monitor.last_error = Some(format!("Failed to access the clipboard: {}", e));
+                return;
+            }
+        };
+        let mut last_emit: Option<Instant> = None;
+        let mut pending_emit: Option<ClipboardEntry> = None;
+        let mut last_selection: Option<String> = None;
+
+        loop {
+            let stopped = {
+                let mut monitor = MONITOR.lock().unwrap();
+                if !monitor.enabled {
+                    monitor.thread_alive = false;
+                }
+                !monitor.enabled
+            };
+            if stopped {
+                // Don't drop a capture that was still waiting for its
+                // interval to pass
+                if let Some(entry) = pending_emit.take() {
+                    let _ = app_handle.emit("clipboard-changed", entry);
+                }
+                break;
+            }
+
+            // While locked, keep track of the clipboard without recording it,
+            // so whatever was set during the lock isn't captured on unlock
+            if current_settings().pause_when_locked && is_screen_locked() {
+                MONITOR.lock().unwrap().last_content = clipboard.get_text().ok();
+                thread::sleep(Duration::from_millis(500));
+                continue;
+            }
+
+            let text = match clipboard.get_text() {
+                Ok(text) => Some(text).filter(|text| !text.is_empty()),
+                // Cleared, or holding something other than text
+                Err(arboard::Error::ContentNotAvailable) => None,
+                Err(e) => {
+                    set_monitor_error(format!("Failed to read the clipboard: {}", e));
+                    thread::sleep(Duration::from_millis(500));
+                    continue;
+                }
+            };
+            let new_text = new_clipboard_text(&mut MONITOR.lock().unwrap().last_content, text);
+
+            if let Some(current) = new_text {
+                // Wait for the clipboard to settle, so apps that write
+                // intermediate states in quick succession record only once.
+                // If it changed meanwhile, the next iteration debounces again.
+                let debounce_ms = current_settings().debounce_ms;
+                if debounce_ms > 0 {
+                    thread::sleep(Duration::from_millis(debounce_ms));
+                    match clipboard.get_text() {
+                        Ok(latest) if latest == current => {}
+                        _ => continue,
+                    }
+                }
+
+                let result = record_capture(&app_handle, &mut clipboard, &current);
+                if let Some(entry) = note_capture_result(result) {
+                    pending_emit = Some(entry);
+                }
+
+                MONITOR.lock().unwrap().last_content = Some(current);
+            }
+
+            // The PRIMARY selection changes while a selection is dragged out,
+            // so it is debounced like the clipboard. Text that is also on the
+            // clipboard was just recorded from there.
+            if current_settings().capture_primary_selection {
+                let selection = read_primary_selection(&mut clipboard)
+                    .filter(|s| last_selection.as_ref() != Some(s));
+                if let Some(selection) = selection {
+                    let debounce_ms = current_settings().debounce_ms;
+                    if debounce_ms > 0 {
+                        thread::sleep(Duration::from_millis(debounce_ms));
+                    }
+                    if read_primary_selection(&mut clipboard).as_ref() == Some(&selection) {
+                        let on_clipboard =
+                            MONITOR.lock().unwrap().last_content.as_ref() == Some(&selection);
+                        if !on_clipboard {
+                            let result = record_selection(&app_handle, &selection);
+                            if let Some(entry) = note_capture_result(result) {
+                                pending_emit = Some(entry);
+                            }
+                        }
+                        last_selection = Some(selection);
+                    }
+                }
+            }
+
+            // Emit at most once per interval so bursts of captures don't flood
+            // the UI. Intermediate captures are coalesced; the latest one is
+            // always emitted once the interval has passed.
+            if let Some(entry) = &pending_emit {
+                let interval = Duration::from_millis(current_settings().emit_interval_ms);
+                if last_emit.map_or(true, |t| t.elapsed() >= interval) {
+                    let _ = app_handle.emit("clipboard-changed", entry);
+                    last_emit = Some(Instant::now());
+                    pending_emit = None;
+                }
+            }
+
+            thread::sleep(Duration::from_millis(500));
+        }
+    });
+}
+
+/// Compares the clipboard `text` read on a poll (`None` when it is empty or
+/// not text) with the last content seen, and returns it if it should be
+/// captured. The caller stores it in `last_content` once it is recorded.
+fn new_clipboard_text(last_content: &mut Option<String>, text: Option<String>) -> Option<String> {
+    match text {
+        Some(text) if last_content.as_ref() == Some(&text) => None,
+        Some(text) => Some(text),
+        // Cleared, or holding something other than text. Forget the last
+        // content so copying the same text again is recorded.
+        None => {
+            *last_content = None;
+            None
+        }
+    }
+}
+
+/// Records freshly read clipboard `text` as a history entry, along with its
+/// HTML flavor and source app. File copies are recorded as their paths, or
+/// skipped unless `record_file_copies` is set. Returns `None` when the
+/// settings say the clipboard shouldn't be recorded.
+fn record_capture(
+    app_handle: &AppHandle,
+    clipboard: &mut Clipboard,
+    text: &str,
+) -> std::io::Result<Option<ClipboardEntry>> {
+    let settings = current_settings();
+    let files = clipboard.get().file_list().unwrap_or_default();
+
+    let entry = if files.is_empty() {
+        let Some(content) = prepare_capture(text, &settings) else {
+            return Ok(None);
+        };
+        let mut entry = ClipboardEntry::new(content);
+        entry.html = clipboard
+            .get()
+            .html()
+            .ok()
+            .filter(|html| !html.trim().is_empty());
+        entry
+    } else if settings.record_file_copies {
+        let paths: Vec<_> = files.iter().map(|path| path.to_string_lossy()).collect();
+        let mut entry = ClipboardEntry::new(paths.join("\n"));
+        entry.kind = ContentKind::Files;
+        entry
+    } else {
+        return Ok(None);
+    };
+    save_capture(app_handle, entry, &settings)
+}
+
+/// Records `text` read from the PRIMARY selection. Unlike the clipboard it
+/// has no HTML or file flavors to look at.
+fn record_selection(app_handle: &AppHandle, text: &str) -> std::io::Result<Option<ClipboardEntry>> {
+    let settings = current_settings();
+    let Some(content) = prepare_capture(text, &settings) else {
+        return Ok(None);
+    };
+    save_capture(app_handle, ClipboardEntry::new(content), &settings)
+}
+
+/// Stores a freshly captured entry along with its source app, unless that
+/// app is excluded from capture. The returned entry is masked like the other
+/// entries handed to the UI.
+fn save_capture(
+    app_handle: &AppHandle,
+    mut entry: ClipboardEntry,
+    settings: &Settings,
+) -> std::io::Result<Option<ClipboardEntry>> {
+    entry.source_app = frontmost_app_name();
+    if is_excluded_app(entry.source_app.as_deref(), settings) {
+        return Ok(None);
+    }
+    save_entry(&entry)?;
+
+    reset_paste_queue_on_capture(&entry.content);
+    mask_sensitive(std::slice::from_mut(&mut entry));
+    if settings.notify_on_capture {
+        notify_capture(app_handle, &entry);
+    }
+    Ok(Some(entry))
+}
+
+/// Whether copies from `app` are not recorded. An `included_apps` match
+/// always records, even when an `excluded_apps` pattern matches too; apps
+/// matching neither list, and copies with no known source app, are recorded.
+/// Patterns match anywhere in the app name, ignoring case.
+fn is_excluded_app(app: Option<&str>, settings: &Settings) -> bool {
+    let Some(app) = app else {
+        return false;
+    };
+    let app = app.to_lowercase();
+    let matches = |patterns: &[String]| {
+        patterns
+            .iter()
+            .map(|p| p.trim())
+            .any(|p| !p.is_empty() && app.contains(&p.to_lowercase()))
+    };
+    !matches(&settings.included_apps) && matches(&settings.excluded_apps)
 }
 
-#[tauri::command]
-fn toggle_pin(timestamp: String, pinned: bool) -> Result<(), String> {
-    let mut history = load_history();
+/// Text of the PRIMARY selection, which X11 and Wayland update whenever text
+/// is selected
+#[cfg(target_os = "linux")]
+fn read_primary_selection(clipboard: &mut Clipboard) -> Option<String> {
+    use arboard::{GetExtLinux, LinuxClipboardKind};
+
+    clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+        .filter(|text| !text.is_empty())
+}
 
-    // Find the entry by timestamp and update its pinned state
-    if let Some(entry) = history
-        .iter_mut()
-        .find(|e| e.timestamp.to_rfc3339() == timestamp)
-    {
-        entry.pinned = pinned;
-    } else {
-        return Err("Entry not found".to_string());
-    }
+#[cfg(not(target_os = "linux"))]
+fn read_primary_selection(_clipboard: &mut Clipboard) -> Option<String> {
+    // Only X11 and Wayland have a PRIMARY selection
+    None
+}
 
-    save_history(&history).map_err(|e| e.to_string())
+/// The text on the clipboard right now, or `None` when it holds no text.
+/// Only peeks: nothing is recorded and the monitor isn't affected.
+#[tauri::command]
+fn read_clipboard() -> Result<Option<String>, String> {
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    Ok(clipboard.get_text().ok().filter(|text| !text.is_empty()))
 }
 
+/// Records the current clipboard right away instead of waiting for the next
+/// poll. Returns the captured entry, or `None` when the clipboard is empty or
+/// its content was already seen.
 #[tauri::command]
-fn clear_all_history() -> Result<(), String> {
-    let history = load_history();
-    let pinned: Vec<_> = history.into_iter().filter(|e| e.pinned).collect();
+fn capture_now(app_handle: AppHandle) -> Result<Option<ClipboardEntry>, String> {
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+    let text = match clipboard.get_text() {
+        Ok(text) if !text.is_empty() => text,
+        _ => return Ok(None),
+    };
+    if MONITOR.lock().unwrap().last_content.as_ref() == Some(&text) {
+        return Ok(None);
+    }
 
-    if pinned.is_empty() {
-        let path = get_history_path();
-        if path.exists() {
-            fs::remove_file(&path).map_err(|e| e.to_string())?;
-        }
-    } else {
-        save_history(&pinned).map_err(|e| e.to_string())?;
+    let entry = record_capture(&app_handle, &mut clipboard, &text).map_err(|e| e.to_string())?;
+    MONITOR.lock().unwrap().last_content = Some(text);
+    if let Some(entry) = &entry {
+        let _ = app_handle.emit("clipboard-changed", entry);
     }
-    Ok(())
+    Ok(entry)
 }
 
+/// Whether the login session is locked or switched away from the console,
+/// as reported by `CGSessionCopyCurrentDictionary`
 #[cfg(target_os = "macos")]
-#[tauri::command]
-fn restore_previous_app() -> Result<(), String> {
-    let previous_app = PREVIOUS_APP.lock().unwrap().clone();
-    if let Some(app) = previous_app {
-        // Use empty options set - the default behavior will activate the app
-        app.activateWithOptions(objc2_app_kit::NSApplicationActivationOptions::empty());
+fn is_screen_locked() -> bool {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::string::CFString;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
     }
-    Ok(())
+
+    let dict_ref = unsafe { CGSessionCopyCurrentDictionary() };
+    if dict_ref.is_null() {
+        return false;
+    }
+    // "Copy" function: we own the returned dictionary
+    let session: CFDictionary<CFString, CFType> =
+        unsafe { CFDictionary::wrap_under_create_rule(dict_ref) };
+    let flag = |key: &'static str| {
+        session
+            .find(CFString::from_static_string(key))
+            .and_then(|value| value.downcast::<CFBoolean>())
+            .map(bool::from)
+    };
+
+    flag("CGSSessionScreenIsLocked").unwrap_or(false)
+        || !flag("kCGSSessionOnConsoleKey").unwrap_or(true)
 }
 
 #[cfg(not(target_os = "macos"))]
-#[tauri::command]
-fn restore_previous_app() -> Result<(), String> {
-    Ok(())
+fn is_screen_locked() -> bool {
+    false
 }
 
-fn start_clipboard_monitor(app_handle: AppHandle, running: Arc<AtomicBool>) {
-    thread::spawn(move || {
-        let mut clipboard = match Clipboard::new() {
-            Ok(c) => c,
-            Err(e) => {
-                log::error!("Failed to access clipboard: {}", e);
-                return;
-            }
-        };
-        let mut last_content: Option<String> = None;
-
-        while running.load(Ordering::Relaxed) {
-            if let Ok(current) = clipboard.get_text() {
-                let is_new = match &last_content {
-                    Some(last) => last != &current,
-                    None => true,
-                };
-
-                if is_new && !current.is_empty() {
-                    let entry = ClipboardEntry {
-                        timestamp: Local::now(),
-                        content: current.clone(),
-                        pinned: false,
-                    };
-
-                    if let Err(e) = save_entry(&entry) {
-                        log::error!("保存エラー: {}", e);
-                    } else {
-                        let _ = app_handle.emit("clipboard-changed", &entry);
-                    }
+/// Localized name of the frontmost application, i.e. the app that most
+/// likely put the current content on the clipboard
+#[cfg(target_os = "macos")]
+fn frontmost_app_name() -> Option<String> {
+    NSWorkspace::sharedWorkspace()
+        .frontmostApplication()
+        .and_then(|app| app.localizedName())
+        .map(|name| name.to_string())
+}
 
-                    last_content = Some(current);
-                }
-            }
+#[cfg(not(target_os = "macos"))]
+fn frontmost_app_name() -> Option<String> {
+    None
+}
 
-            thread::sleep(Duration::from_millis(500));
-        }
-    });
+/// Shows the window at the mouse once the frontend has sized it for
+/// autosize_popup
+#[tauri::command]
+fn show_popup(app_handle: AppHandle) {
+    show_window_at_mouse(&app_handle);
 }
 
 fn show_window_at_mouse(app_handle: &AppHandle) {
@@ -261,9 +2588,12 @@ fn show_window_at_mouse(app_handle: &AppHandle) {
 
         // Get the current mouse position using CGEvent (macOS)
         // CGEvent returns coordinates in the global display coordinate system (top-left origin)
-        // which works correctly with multiple monitors
+        // which works correctly with multiple monitors.
+        // With `LastPosition` the window simply reappears where it was hidden.
         #[cfg(target_os = "macos")]
-        {
+        let popup_position = current_settings().popup_position;
+        #[cfg(target_os = "macos")]
+        if popup_position != PopupPosition::LastPosition {
             use core_graphics::display::CGDisplay;
             use core_graphics::event::CGEvent;
             use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
@@ -327,9 +2657,15 @@ fn show_window_at_mouse(app_handle: &AppHandle) {
                         })
                     };
 
-                    // Calculate initial position (centered horizontally on cursor, slightly below)
-                    let mut new_x = mouse_x - window_width / 2;
-                    let mut new_y = mouse_y + 10;
+                    let (mut new_x, mut new_y) = match popup_position {
+                        // Centered on the display containing the cursor
+                        PopupPosition::ScreenCenter => (
+                            screen_x + (screen_width - window_width) / 2,
+                            screen_y + (screen_height - window_height) / 2,
+                        ),
+                        // Centered horizontally on cursor, slightly below
+                        _ => (mouse_x - window_width / 2, mouse_y + 10),
+                    };
 
                     // Clamp to screen bounds with margins
                     let menu_bar_height = 25;
@@ -363,57 +2699,132 @@ fn show_window_at_mouse(app_handle: &AppHandle) {
         }
 
         let _ = window.show();
-        let _ = window.set_focus();
+        // Without focus the window appears without interrupting typing in
+        // another app; the user clicks into it
+        if current_settings().steal_focus_on_show {
+            let _ = window.set_focus();
+        }
     }
 }
 
+/// Tracks Option key presses and decides when they form the configured
+/// trigger. A press combined with another modifier (e.g. Option+Cmd) never
+/// counts, so modifier shortcuts don't fire the trigger by accident.
 #[cfg(target_os = "macos")]
-fn start_hotkey_listener(app_handle: AppHandle) {
-    use std::sync::Mutex;
+struct TriggerDetector {
+    pressed_at: Option<Instant>,
+    /// Another modifier was held at some point during the current press
+    combo: bool,
+    tap_count: u32,
+    last_release: Option<Instant>,
+}
+
+#[cfg(target_os = "macos")]
+impl TriggerDetector {
+    const fn new() -> Self {
+        TriggerDetector {
+            pressed_at: None,
+            combo: false,
+            tap_count: 0,
+            last_release: None,
+        }
+    }
+
+    /// Feeds a modifier-flags change. Returns true when the trigger fired.
+    fn on_flags_changed(
+        &mut self,
+        option_pressed: bool,
+        other_modifiers: bool,
+        mode: TriggerMode,
+    ) -> bool {
+        let now = Instant::now();
+
+        if option_pressed {
+            if self.pressed_at.is_none() {
+                self.pressed_at = Some(now);
+                self.combo = other_modifiers;
+            } else if other_modifiers {
+                self.combo = true;
+            }
+            return false;
+        }
+
+        let Some(pressed_at) = self.pressed_at.take() else {
+            // Another modifier alone breaks a tap sequence
+            if other_modifiers {
+                self.reset_taps();
+            }
+            return false;
+        };
+        if self.combo {
+            self.reset_taps();
+            return false;
+        }
+
+        match mode {
+            TriggerMode::LongPress(ms) => now.duration_since(pressed_at).as_millis() >= ms as u128,
+            TriggerMode::DoubleTap | TriggerMode::TripleTap => {
+                let taps_needed = if mode == TriggerMode::DoubleTap { 2 } else { 3 };
+                let in_sequence = self.last_release.is_some_and(|last| {
+                    now.duration_since(last).as_millis() < DOUBLE_TAP_THRESHOLD_MS
+                });
+                self.tap_count = if in_sequence { self.tap_count + 1 } else { 1 };
+                self.last_release = Some(now);
+
+                if self.tap_count >= taps_needed {
+                    self.reset_taps();
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn reset_taps(&mut self) {
+        self.tap_count = 0;
+        self.last_release = None;
+    }
+}
 
-    println!("[Recall] Starting hotkey listener with NSEvent...");
+#[cfg(target_os = "macos")]
+fn start_hotkey_listener(app_handle: AppHandle) {
+    log::info!("Starting hotkey listener with NSEvent...");
 
-    // Use static variables wrapped in Mutex for thread safety
-    static LAST_OPTION_RELEASE: Mutex<Option<Instant>> = Mutex::new(None);
-    static LAST_TRIGGER: Mutex<Option<Instant>> = Mutex::new(None);
-    static OPTION_WAS_PRESSED: Mutex<bool> = Mutex::new(false);
+    // Shared by the global and local monitors so a tap sequence can span both
+    static TRIGGER: Mutex<TriggerDetector> = Mutex::new(TriggerDetector::new());
 
     // Store app_handle in a thread-safe way
     static APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
     *APP_HANDLE.lock().unwrap() = Some(app_handle);
 
+    fn handle_flags_changed(event: &NSEvent, monitor: &str) {
+        let flags = event.modifierFlags();
+        let option_pressed = flags.contains(NSEventModifierFlags::Option);
+        let other_modifiers = flags.intersects(
+            NSEventModifierFlags::Shift
+                | NSEventModifierFlags::Control
+                | NSEventModifierFlags::Command,
+        );
+        let mode = current_settings().trigger_mode;
+
+        if TRIGGER
+            .lock()
+            .unwrap()
+            .on_flags_changed(option_pressed, other_modifiers, mode)
+        {
+            log::info!("Option {:?} detected ({})", mode, monitor);
+            if let Some(ref handle) = *APP_HANDLE.lock().unwrap() {
+                let _ = handle.emit("show-window-at-mouse", ());
+            }
+        }
+    }
+
     // Run on separate thread since NSRunLoop blocks
     thread::spawn(move || {
         // Global monitor for when other apps are focused
         let global_block = StackBlock::new(|event: NonNull<NSEvent>| {
-            let event = unsafe { event.as_ref() };
-            let modifier_flags = event.modifierFlags();
-            let option_pressed = modifier_flags.contains(NSEventModifierFlags::Option);
-
-            let mut was_pressed = OPTION_WAS_PRESSED.lock().unwrap();
-            let mut last_release = LAST_OPTION_RELEASE.lock().unwrap();
-            let mut last_trigger = LAST_TRIGGER.lock().unwrap();
-
-            if option_pressed {
-                *was_pressed = true;
-            } else if *was_pressed {
-                *was_pressed = false;
-                let now = Instant::now();
-
-                if let Some(last) = *last_release {
-                    let elapsed = now.duration_since(last).as_millis();
-                    if elapsed < DOUBLE_TAP_THRESHOLD_MS {
-                        println!("[Recall] Option double tap detected!");
-                        if let Some(ref handle) = *APP_HANDLE.lock().unwrap() {
-                            let _ = handle.emit("show-window-at-mouse", ());
-                        }
-                        *last_release = None;
-                        *last_trigger = Some(now);
-                        return;
-                    }
-                }
-                *last_release = Some(now);
-            }
+            handle_flags_changed(unsafe { event.as_ref() }, "global");
         });
 
         let _ = NSEvent::addGlobalMonitorForEventsMatchingMask_handler(
@@ -423,34 +2834,7 @@ fn start_hotkey_listener(app_handle: AppHandle) {
 
         // Local monitor for when our app is focused
         let local_block = StackBlock::new(|event: NonNull<NSEvent>| -> *mut NSEvent {
-            let event_ref = unsafe { event.as_ref() };
-            let modifier_flags = event_ref.modifierFlags();
-            let option_pressed = modifier_flags.contains(NSEventModifierFlags::Option);
-
-            let mut was_pressed = OPTION_WAS_PRESSED.lock().unwrap();
-            let mut last_release = LAST_OPTION_RELEASE.lock().unwrap();
-            let mut last_trigger = LAST_TRIGGER.lock().unwrap();
-
-            if option_pressed {
-                *was_pressed = true;
-            } else if *was_pressed {
-                *was_pressed = false;
-                let now = Instant::now();
-
-                if let Some(last) = *last_release {
-                    let elapsed = now.duration_since(last).as_millis();
-                    if elapsed < DOUBLE_TAP_THRESHOLD_MS {
-                        println!("[Recall] Option double tap detected (local)!");
-                        if let Some(ref handle) = *APP_HANDLE.lock().unwrap() {
-                            let _ = handle.emit("show-window-at-mouse", ());
-                        }
-                        *last_release = None;
-                        *last_trigger = Some(now);
-                    }
-                } else {
-                    *last_release = Some(now);
-                }
-            }
+            handle_flags_changed(unsafe { event.as_ref() }, "local");
             // Return the event as-is
             event.as_ptr()
         });
@@ -464,7 +2848,56 @@ fn start_hotkey_listener(app_handle: AppHandle) {
             );
         }
 
-        println!("[Recall] NSEvent global and local monitors registered");
+        // Global monitor for the Cmd+Shift+1..9 slot shortcuts and the
+        // Ctrl+Option+V copy-previous shortcut. Global monitors can't swallow
+        // events, so the keystroke still reaches the focused app.
+        let slot_block = StackBlock::new(|event: NonNull<NSEvent>| {
+            let event = unsafe { event.as_ref() };
+            if event.isARepeat() {
+                return;
+            }
+
+            let settings = current_settings();
+            let modifiers = event.modifierFlags()
+                & (NSEventModifierFlags::Shift
+                    | NSEventModifierFlags::Control
+                    | NSEventModifierFlags::Option
+                    | NSEventModifierFlags::Command);
+            let app_handle = APP_HANDLE.lock().unwrap();
+            let Some(ref handle) = *app_handle else {
+                return;
+            };
+            let toggle_monitoring = settings
+                .toggle_monitoring_enabled
+                .then(|| Shortcut::parse(&settings.toggle_monitoring_shortcut).ok())
+                .flatten();
+
+            // Checked first so a custom shortcut wins over the built-in ones
+            if let Some(shortcut) = toggle_monitoring
+                .filter(|s| modifiers == shortcut_modifiers(s) && event.keyCode() == s.key_code)
+            {
+                log::info!("Monitoring toggle shortcut {:?} pressed", shortcut);
+                let _ = handle.emit("toggle-monitoring", ());
+            } else if settings.slot_shortcuts_enabled
+                && modifiers == NSEventModifierFlags::Shift | NSEventModifierFlags::Command
+            {
+                if let Some(slot) = SLOT_KEY_CODES.iter().position(|&c| c == event.keyCode()) {
+                    let _ = handle.emit("paste-slot", slot);
+                }
+            } else if settings.copy_nth_enabled
+                && modifiers == NSEventModifierFlags::Control | NSEventModifierFlags::Option
+                && event.keyCode() == KEY_CODE_V
+            {
+                let _ = handle.emit("copy-nth", ());
+            }
+        });
+
+        let _ = NSEvent::addGlobalMonitorForEventsMatchingMask_handler(
+            NSEventMask::KeyDown,
+            &slot_block,
+        );
+
+        log::info!("NSEvent global and local monitors registered");
 
         // Keep the thread alive and run the event loop
         let run_loop = NSRunLoop::currentRunLoop();
@@ -472,6 +2905,23 @@ fn start_hotkey_listener(app_handle: AppHandle) {
     });
 }
 
+/// The NSEvent modifier flags a shortcut is pressed with
+#[cfg(target_os = "macos")]
+fn shortcut_modifiers(shortcut: &Shortcut) -> NSEventModifierFlags {
+    let mut flags = NSEventModifierFlags::empty();
+    for (held, flag) in [
+        (shortcut.shift, NSEventModifierFlags::Shift),
+        (shortcut.control, NSEventModifierFlags::Control),
+        (shortcut.option, NSEventModifierFlags::Option),
+        (shortcut.command, NSEventModifierFlags::Command),
+    ] {
+        if held {
+            flags |= flag;
+        }
+    }
+    flags
+}
+
 #[cfg(not(target_os = "macos"))]
 fn start_hotkey_listener(_app_handle: AppHandle) {
     // No-op on non-macOS platforms
@@ -479,8 +2929,7 @@ fn start_hotkey_listener(_app_handle: AppHandle) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let running = Arc::new(AtomicBool::new(true));
-    let running_clone = running.clone();
+    logger::init();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {
@@ -488,16 +2937,82 @@ pub fn run() {
             // Window should only be shown via Option key double-tap
         }))
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             get_history,
+            get_history_by_kind,
+            get_kind_summary,
+            get_source_apps,
+            extract_urls,
+            find_duplicates,
+            get_history_by_app,
+            search_history,
+            search_history_regex,
+            get_history_in_range,
             copy_to_clipboard,
+            copy_plain,
+            add_manual_entry,
+            get_paste_queue,
+            next_in_queue,
             toggle_pin,
+            pin_by_content,
+            get_pinned,
             clear_all_history,
-            restore_previous_app
+            trim_history,
+            clear_by_kind,
+            delete_entry,
+            bump_entry,
+            undo_last_removal,
+            get_trash,
+            restore_from_trash,
+            get_stats,
+            open_data_dir,
+            get_history_file_path,
+            get_data_dir_path,
+            backup,
+            restore,
+            export_markdown,
+            get_settings,
+            update_settings,
+            toggle_always_on_top,
+            switch_profile,
+            restore_previous_app,
+            show_popup,
+            set_monitoring_enabled,
+            capture_now,
+            diff_entries,
+            reveal_entry,
+            reload,
+            get_largest_entries,
+            read_clipboard,
+            verify_history,
+            copy_multiple,
+            get_history_min_length,
+            format_json,
+            set_label,
+            get_history_since,
+            get_recent,
+            set_note,
+            migrate_data_dir,
+            get_last_monitor_error,
+            is_monitoring_enabled
         ])
-        .setup(move |app| {
+        .setup(|app| {
+            let settings = current_settings();
+            apply_window_settings(app.handle(), &settings);
+            // The window is created invisible (tauri.conf.json), so nothing
+            // flashes on a cold start unless it's asked for
+            if !settings.start_hidden {
+                show_window_centered(app.handle());
+            }
+
+            if is_first_run() {
+                handle_first_run(app.handle());
+            }
+
             // Start clipboard monitoring
-            start_clipboard_monitor(app.handle().clone(), running_clone.clone());
+            start_clipboard_monitor(app.handle().clone());
+            start_file_watcher(app.handle().clone());
 
             // Start hotkey listener for Option key double-tap
             start_hotkey_listener(app.handle().clone());
@@ -505,9 +3020,36 @@ pub fn run() {
             // Listen for show-window-at-mouse event from hotkey listener
             let app_handle = app.handle().clone();
             app.listen("show-window-at-mouse", move |_| {
-                show_window_at_mouse(&app_handle);
+                // Catch a copy made since the last poll, so it's already listed
+                if MONITOR.lock().unwrap().enabled {
+                    if let Err(e) = capture_now(app_handle.clone()) {
+                        log::error!("Failed to capture clipboard: {}", e);
+                    }
+                }
+                // With autosize_popup the frontend fits the window to the
+                // history first and calls show_popup, so it doesn't resize
+                // in view
+                if current_settings().autosize_popup {
+                    let _ = app_handle.emit("fit-popup", ());
+                } else {
+                    show_window_at_mouse(&app_handle);
+                }
             });
 
+            // Listen for paste-slot events from the Cmd+Shift+1..9 shortcuts
+            app.listen("paste-slot", |event| {
+                if let Ok(slot) = serde_json::from_str::<usize>(event.payload()) {
+                    paste_slot(slot);
+                }
+            });
+
+            // Listen for the Ctrl+Option+V copy-previous shortcut
+            app.listen("copy-nth", |_| copy_nth_entry());
+
+            // Listen for the pause/resume monitoring shortcut
+            let app_handle = app.handle().clone();
+            app.listen("toggle-monitoring", move |_| toggle_monitoring(&app_handle));
+
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -517,7 +3059,7 @@ pub fn run() {
                     let _ = window.hide();
                     api.prevent_close();
                 }
-                tauri::WindowEvent::Focused(false) => {
+                tauri::WindowEvent::Focused(false) if current_settings().hide_on_blur => {
                     // Hide window when it loses focus (Spotlight-like behavior)
                     let _ = window.hide();
                 }
@@ -526,14 +3068,400 @@ pub fn run() {
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|app, event| {
-            if let tauri::RunEvent::Reopen { .. } = event {
-                // Dock icon clicked
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.center();
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
-            }
+        .run(|app, event| match event {
+            // Dock icon clicked
+            tauri::RunEvent::Reopen { .. } => show_window_centered(app),
+            tauri::RunEvent::Exit => flush_pending_uses(),
+            _ => {}
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn parse(line: &str) -> ClipboardEntry {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn migrates_v1_line() {
+        let mut entry =
+            parse(r#"{"timestamp":"2024-01-02T03:04:05+09:00","content":"hello","pinned":false}"#);
+        assert_eq!(entry.v, 1);
+        assert!(entry.id.is_empty());
+
+        assert!(migrate_entry(&mut entry));
+        assert!(!entry.id.is_empty());
+        assert_eq!(entry.kind, ContentKind::Text);
+        assert_eq!(entry.v, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrates_v2_line() {
+        let mut entry = parse(
+            r#"{"v":2,"id":"abc","timestamp":"2024-01-02T03:04:05Z","content":"https://example.com","pinned":true}"#,
+        );
+
+        assert!(migrate_entry(&mut entry));
+        assert_eq!(entry.id, "abc");
+        assert_eq!(entry.kind, ContentKind::Url);
+        assert!(entry.pinned);
+        assert_eq!(entry.v, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrates_v3_line() {
+        let mut entry = parse(
+            r#"{"v":3,"id":"abc","timestamp":"2024-01-02T03:04:05+09:00","content":"x","pinned":false,"kind":"Code"}"#,
+        );
+
+        assert!(migrate_entry(&mut entry));
+        assert_eq!(entry.id, "abc");
+        // Already classified; v3 -> v4 only changes how timestamps are written
+        assert_eq!(entry.kind, ContentKind::Code);
+        assert_eq!(entry.v, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn current_version_is_not_migrated() {
+        let mut entry = ClipboardEntry::new("hello".to_string());
+        assert!(!migrate_entry(&mut entry));
+    }
+
+    #[test]
+    fn local_offset_timestamps_are_written_as_utc() {
+        let mut entry =
+            parse(r#"{"timestamp":"2024-01-02T03:04:05+09:00","content":"hello","pinned":false}"#);
+        let expected = Utc.with_ymd_and_hms(2024, 1, 1, 18, 4, 5).unwrap();
+        assert_eq!(entry.timestamp.with_timezone(&Utc), expected);
+
+        migrate_entry(&mut entry);
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(
+            line.contains(r#""timestamp":"2024-01-01T18:04:05Z""#),
+            "{}",
+            line
+        );
+
+        let reparsed = parse(&line);
+        assert_eq!(reparsed.timestamp, entry.timestamp);
+        assert_eq!(reparsed.id, entry.id);
+        assert_eq!(reparsed.v, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn line_endings_normalize_to_one_entry() {
+        let settings = Settings::default();
+        let windows = prepare_capture("a\r\nb", &settings).unwrap();
+        let unix = prepare_capture("a\nb", &settings).unwrap();
+
+        assert_eq!(windows, "a\nb");
+        assert_eq!(
+            dedup_key(&ClipboardEntry::new(windows), &settings),
+            dedup_key(&ClipboardEntry::new(unix), &settings)
+        );
+    }
+
+    #[test]
+    fn whitespace_only_capture_is_skipped() {
+        assert_eq!(prepare_capture("  \n ", &Settings::default()), None);
+    }
+
+    #[test]
+    fn trim_on_capture_trims_padding() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            prepare_capture("  padded \n", &settings).unwrap(),
+            "  padded \n"
+        );
+
+        settings.trim_on_capture = true;
+        assert_eq!(prepare_capture("  padded \n", &settings).unwrap(), "padded");
+    }
+
+    #[test]
+    fn whitespace_runs_dedup_when_collapsing() {
+        let mut settings = Settings::default();
+        assert_ne!(
+            dedup_key(&ClipboardEntry::new("a  b".to_string()), &settings),
+            dedup_key(&ClipboardEntry::new("a b".to_string()), &settings)
+        );
+
+        settings.collapse_whitespace_dupes = true;
+        assert_eq!(
+            dedup_key(&ClipboardEntry::new("a  b".to_string()), &settings),
+            dedup_key(&ClipboardEntry::new("a b".to_string()), &settings)
+        );
+    }
+
+    #[test]
+    fn letter_case_dedups_when_case_insensitive() {
+        let mut settings = Settings::default();
+        assert_ne!(
+            dedup_key(&ClipboardEntry::new("Hello".to_string()), &settings),
+            dedup_key(&ClipboardEntry::new("hello".to_string()), &settings)
+        );
+
+        settings.case_insensitive_dedup = true;
+        assert_eq!(
+            dedup_key(&ClipboardEntry::new("Hello".to_string()), &settings),
+            dedup_key(&ClipboardEntry::new("hello".to_string()), &settings)
+        );
+    }
+
+    #[test]
+    fn captures_below_min_chars_are_skipped() {
+        let settings = Settings {
+            min_entry_chars: 2,
+            ..Settings::default()
+        };
+        assert_eq!(prepare_capture("x", &settings), None);
+        assert_eq!(prepare_capture("xy", &settings).unwrap(), "xy");
+        // Characters, not bytes
+        assert_eq!(prepare_capture("é", &settings), None);
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        let text = "héllo, world\n";
+        let encoded = apply_transform(text, Transform::Base64Encode).unwrap();
+        assert_eq!(
+            apply_transform(&encoded, Transform::Base64Decode).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn url_encoding_round_trips() {
+        let text = "a b&c=d/é?";
+        let encoded = apply_transform(text, Transform::UrlEncode).unwrap();
+        assert!(!encoded.contains(' '));
+        assert_eq!(
+            apply_transform(&encoded, Transform::UrlDecode).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn invalid_base64_is_an_error() {
+        let error = apply_transform("not base64!", Transform::Base64Decode).unwrap_err();
+        assert!(error.starts_with("Invalid base64"), "{}", error);
+    }
+
+    #[test]
+    fn non_utf8_base64_is_an_error() {
+        // 0xFF 0xFE
+        let error = apply_transform("//4=", Transform::Base64Decode).unwrap_err();
+        assert_eq!(error, "Decoded base64 is not valid UTF-8 text");
+    }
+
+    /// Feeds clipboard reads to `new_clipboard_text` like the monitor does,
+    /// returning what would be captured
+    fn poll_clipboard(reads: &[Option<&str>]) -> Vec<String> {
+        let mut last_content = None;
+        let mut captured = Vec::new();
+        for read in reads {
+            let text = read.map(str::to_string);
+            if let Some(text) = new_clipboard_text(&mut last_content, text) {
+                last_content = Some(text.clone());
+                captured.push(text);
+            }
+        }
+        captured
+    }
+
+    #[test]
+    fn unchanged_clipboard_is_captured_once() {
+        assert_eq!(poll_clipboard(&[Some("a"), Some("a")]), ["a"]);
+    }
+
+    #[test]
+    fn same_text_after_clear_is_captured_again() {
+        assert_eq!(poll_clipboard(&[Some("a"), None, Some("a")]), ["a", "a"]);
+    }
+
+    #[test]
+    fn new_text_after_clear_is_captured() {
+        assert_eq!(poll_clipboard(&[Some("a"), None, Some("b")]), ["a", "b"]);
+    }
+
+    /// `count` pinned entries, pinned one minute apart starting with the first
+    fn pinned_entries(count: usize) -> Vec<ClipboardEntry> {
+        let start = Local::now();
+        (0..count)
+            .map(|i| {
+                let mut entry = ClipboardEntry::new(format!("entry {}", i));
+                entry.pinned = true;
+                entry.pinned_at = Some(start + chrono::Duration::minutes(i as i64));
+                entry
+            })
+            .collect()
+    }
+
+    fn pin_settings(max_pinned: usize, unpin_oldest_when_full: bool) -> Settings {
+        Settings {
+            max_pinned,
+            unpin_oldest_when_full,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn pinning_below_max_needs_no_room() {
+        let mut history = pinned_entries(2);
+        assert!(make_room_for_pin(&mut history, &pin_settings(3, false)).is_ok());
+        assert!(history.iter().all(|e| e.pinned));
+    }
+
+    #[test]
+    fn pinning_at_max_is_refused() {
+        let mut history = pinned_entries(3);
+        assert!(make_room_for_pin(&mut history, &pin_settings(3, false)).is_err());
+        assert!(history.iter().all(|e| e.pinned));
+    }
+
+    #[test]
+    fn pinning_at_max_unpins_the_oldest() {
+        let mut history = pinned_entries(3);
+        assert!(make_room_for_pin(&mut history, &pin_settings(3, true)).is_ok());
+
+        assert!(!history[0].pinned);
+        assert_eq!(history[0].pinned_at, None);
+        assert!(history[1].pinned && history[2].pinned);
+    }
+
+    #[test]
+    fn zero_max_pinned_has_no_limit() {
+        let mut history = pinned_entries(50);
+        assert!(make_room_for_pin(&mut history, &pin_settings(0, false)).is_ok());
+        assert!(history.iter().all(|e| e.pinned));
+    }
+
+    /// A fresh, empty directory under the system temp directory
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("recall-test-{}", new_entry_id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_or_empty_dir_is_unused() {
+        let dir = temp_dir();
+        assert!(is_unused_dir(&dir, false));
+        assert!(is_unused_dir(&dir.join("missing"), false));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pointer_file_only_counts_outside_the_default_dir() {
+        let dir = temp_dir();
+        fs::write(dir.join(DATA_DIR_POINTER), "/elsewhere").unwrap();
+        assert!(is_unused_dir(&dir, true));
+        assert!(!is_unused_dir(&dir, false));
+
+        fs::write(dir.join("clipboard_history.jsonl"), "").unwrap();
+        assert!(!is_unused_dir(&dir, true));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removing_copies_keeps_other_files() {
+        let dir = temp_dir();
+        fs::write(dir.join(DATA_DIR_POINTER), "/elsewhere").unwrap();
+        fs::create_dir(dir.join("logs")).unwrap();
+        fs::write(dir.join("logs").join("recall.log"), "").unwrap();
+        fs::write(dir.join("settings.json"), "{}").unwrap();
+
+        let copies = [
+            PathBuf::from("logs/recall.log"),
+            PathBuf::from("settings.json"),
+        ];
+        remove_copies(&dir, &copies);
+        assert!(!dir.join("logs").exists());
+        assert!(!dir.join("settings.json").exists());
+        assert!(dir.join(DATA_DIR_POINTER).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A history with one entry hidden by a mask pattern, as the UI gets it
+    fn masked_history() -> Vec<ClipboardEntry> {
+        let mask = build_mask_set(&[r"secret-\d+".to_string()]).unwrap();
+        let mut history = vec![
+            ClipboardEntry::new("token secret-123 https://secret.example.com".to_string()),
+            ClipboardEntry::new("https://example.com".to_string()),
+        ];
+        mask_entries(&mut history, Some(&mask));
+        history
+    }
+
+    #[test]
+    fn masked_entries_hide_their_content() {
+        let history = masked_history();
+        assert!(history[0].masked);
+        assert_eq!(history[0].content, MASKED_CONTENT);
+        assert!(!history[1].masked);
+    }
+
+    #[test]
+    fn search_does_not_match_masked_content() {
+        for fuzzy in [false, true] {
+            let results = search_entries(masked_history(), "secret", fuzzy);
+            assert!(results.is_empty(), "fuzzy: {}", fuzzy);
+        }
+    }
+
+    #[test]
+    fn urls_of_masked_entries_are_left_out() {
+        assert_eq!(distinct_urls(&masked_history()), ["https://example.com"]);
+    }
+
+    #[test]
+    fn diff_of_masked_entry_leaves_out_the_secret() {
+        let history = masked_history();
+        let diff = diff_contents(&history, &history[0].id, &history[1].id).unwrap();
+        assert!(!diff.contains("secret"), "{}", diff);
+    }
+
+    #[test]
+    fn markdown_export_leaves_out_the_secret() {
+        let markdown = export::render_markdown(&masked_history());
+        assert!(!markdown.contains("secret"), "{}", markdown);
+    }
+
+    fn backup_with_settings(settings: &str) -> String {
+        format!(
+            r#"{{"version":1,"created":"2024-01-01T00:00:00Z","settings":{},"history":[]}}"#,
+            settings
+        )
+    }
+
+    #[test]
+    fn restored_settings_are_validated() {
+        assert!(backup::parse_backup(&backup_with_settings("{}")).is_ok());
+        for invalid in [
+            r#"{"mask_patterns":["("]}"#,
+            r#"{"popup_font_size":200}"#,
+            r#"{"popup_font_family":"x;}"}"#,
+            r#"{"toggle_monitoring_enabled":true,"toggle_monitoring_shortcut":"Nope+Q"}"#,
+            r#"{"profile":"../escape"}"#,
+        ] {
+            assert!(
+                backup::parse_backup(&backup_with_settings(invalid)).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn file_copies_dont_dedup_with_text() {
+        let settings = Settings::default();
+        let text = ClipboardEntry::new("/tmp/report.pdf".to_string());
+        let mut files = ClipboardEntry::new("/tmp/report.pdf".to_string());
+        files.kind = ContentKind::Files;
+
+        assert_ne!(dedup_key(&text, &settings), dedup_key(&files, &settings));
+    }
+}
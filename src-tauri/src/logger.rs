@@ -0,0 +1,100 @@
+use chrono::Local;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::settings::current_settings;
+use crate::{get_data_dir, DATA_LOCK};
+
+/// Rotate `recall.log` once it grows past this size
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Number of rotated files kept (`recall.log.1` is the newest)
+const MAX_ROTATED_FILES: usize = 3;
+
+/// Logs to stderr, and additionally to `logs/recall.log` in the data
+/// directory when the `log_to_file` setting is enabled.
+struct Logger {
+    // Serializes file writes so rotation never races with an append
+    file_lock: Mutex<()>,
+}
+
+static LOGGER: Logger = Logger {
+    file_lock: Mutex::new(()),
+};
+
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
+
+fn get_log_dir() -> PathBuf {
+    get_data_dir().join("logs")
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Our own diagnostics at info, dependencies only when something is wrong
+        if metadata.target().starts_with("recall") {
+            metadata.level() <= Level::Info
+        } else {
+            metadata.level() <= Level::Warn
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} [{}] {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.args()
+        );
+        eprintln!("{}", line);
+
+        if current_settings().log_to_file {
+            let result = {
+                let _guard = self.file_lock.lock().unwrap();
+                let _data = DATA_LOCK.lock().unwrap();
+                append_line(&get_log_dir(), &line)
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to write log file: {}", e);
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn append_line(dir: &Path, line: &str) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    rotate_if_needed(dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("recall.log"))?;
+    writeln!(file, "{}", line)
+}
+
+fn rotate_if_needed(dir: &Path) -> std::io::Result<()> {
+    let path = dir.join("recall.log");
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size < MAX_LOG_BYTES {
+        return Ok(());
+    }
+
+    // Shift recall.log.N -> recall.log.N+1, dropping the oldest
+    for i in (1..MAX_ROTATED_FILES).rev() {
+        let from = dir.join(format!("recall.log.{}", i));
+        if from.exists() {
+            fs::rename(&from, dir.join(format!("recall.log.{}", i + 1)))?;
+        }
+    }
+    fs::rename(&path, dir.join("recall.log.1"))
+}
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::{get_data_dir, remember_own_write, DATA_LOCK};
+
+/// How the Option key summons the history window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerMode {
+    #[default]
+    DoubleTap,
+    TripleTap,
+    /// Hold Option alone for at least this many milliseconds, then release
+    LongPress(u64),
+}
+
+/// Where the history window appears when summoned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PopupPosition {
+    /// Just below the mouse cursor
+    #[default]
+    AtMouse,
+    /// Centered on the display containing the cursor
+    ScreenCenter,
+    /// Wherever the window was last shown
+    LastPosition,
+}
+
+/// Which unpinned entry is dropped first once the history is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    /// The oldest captured entry
+    #[default]
+    Fifo,
+    /// The entry least recently copied back out of the history
+    Lru,
+}
+
+/// Color scheme of the history window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the OS appearance
+    Auto,
+    Light,
+    #[default]
+    Dark,
+}
+
+/// User settings stored in `settings.json` next to the history file.
+/// Missing fields fall back to their defaults, so older files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Convert `\r\n` and lone `\r` to `\n` before storing captured text
+    pub normalize_line_endings: bool,
+    /// Strip leading/trailing whitespace from captured text
+    pub trim_on_capture: bool,
+    /// Also write log output to rotated files under `logs/` in the data directory
+    pub log_to_file: bool,
+    /// Cmd+Shift+1..9 paste the 1st-9th most recent entries. Off by default
+    /// since Cmd+Shift+3/4/5 are also the system screenshot shortcuts.
+    pub slot_shortcuts_enabled: bool,
+    /// Treat entries that differ only in whitespace runs as duplicates.
+    /// Opt-in because whitespace is significant in code.
+    pub collapse_whitespace_dupes: bool,
+    /// Treat entries that differ only in letter case as duplicates; the most
+    /// recently captured casing is kept. Opt-in because case often matters.
+    pub case_insensitive_dedup: bool,
+    /// Ctrl+Option+V copies an earlier entry without opening the window
+    pub copy_nth_enabled: bool,
+    /// How many places before the newest entry Ctrl+Option+V copies from
+    /// (1 = the previous copy)
+    pub copy_nth_offset: usize,
+    /// Also paste the entry copied by Ctrl+Option+V
+    pub copy_nth_paste: bool,
+    /// A global shortcut pauses and resumes clipboard monitoring
+    pub toggle_monitoring_enabled: bool,
+    /// Key combination for pausing/resuming monitoring, e.g. `Ctrl+Option+M`
+    pub toggle_monitoring_shortcut: String,
+    /// Shortcut pastes type the content key by key instead of pressing
+    /// Cmd+V, for apps that block pasting. Long content takes a while.
+    pub paste_as_typing: bool,
+    /// Keep the history window above other windows
+    pub always_on_top: bool,
+    /// Hide the history window when it loses focus (Spotlight-like)
+    pub hide_on_blur: bool,
+    /// Focus the history window when it is summoned, so it can be driven
+    /// from the keyboard right away; turn off to have it appear without
+    /// interrupting typing in another app
+    pub steal_focus_on_show: bool,
+    /// Hide the history window once an entry is copied from it; turn off to
+    /// copy several entries in a row
+    pub close_after_copy: bool,
+    /// Keep the history window hidden at launch until it is summoned;
+    /// otherwise it is shown centered once the app has started
+    pub start_hidden: bool,
+    /// Name of the active history profile; empty for the default history file
+    pub profile: String,
+    /// Show a desktop notification when new content is captured
+    pub notify_on_capture: bool,
+    /// Rank popup search results with a fuzzy matcher instead of substrings
+    pub fuzzy_search: bool,
+    /// Option key gesture that opens the history window
+    pub trigger_mode: TriggerMode,
+    /// Only record a change once the clipboard has stayed the same for this
+    /// many milliseconds; 0 disables debouncing
+    pub debounce_ms: u64,
+    /// When the app that wrote the newest entry writes again within this many
+    /// milliseconds, the new value replaces that entry instead of adding one.
+    /// 0 disables collapsing.
+    pub collapse_consecutive_same_source_ms: u64,
+    /// Minimum milliseconds between `clipboard-changed` events; captures in
+    /// between are coalesced into one event. 0 emits every capture.
+    pub emit_interval_ms: u64,
+    /// Skip captures shorter than this many characters; 0 records everything
+    pub min_entry_chars: usize,
+    /// Record file copies (e.g. from Finder) as a list of their paths.
+    /// Off by default: their text flavor is usually just the file names.
+    pub record_file_copies: bool,
+    /// Also record the PRIMARY selection, i.e. selected text that
+    /// middle-click pastes (Linux)
+    pub capture_primary_selection: bool,
+    /// On first launch, record what's already on the clipboard so the
+    /// history doesn't start out empty
+    pub first_run_capture: bool,
+    /// Don't record copies made in apps whose name contains one of these,
+    /// ignoring case (e.g. a password manager)
+    pub excluded_apps: Vec<String>,
+    /// Always record copies from apps whose name contains one of these, even
+    /// when they also match `excluded_apps`
+    pub included_apps: Vec<String>,
+    /// Don't record anything while the screen is locked (macOS)
+    pub pause_when_locked: bool,
+    /// Days a deleted entry stays in the trash before it is purged
+    pub trash_retention_days: u32,
+    /// Most entries that can be pinned at once; 0 means no limit
+    pub max_pinned: usize,
+    /// When pinning past `max_pinned`, unpin the oldest pinned entry instead
+    /// of refusing
+    pub unpin_oldest_when_full: bool,
+    /// Which unpinned entry is dropped first once the history is full
+    pub eviction_policy: EvictionPolicy,
+    /// Regexes for sensitive content. Matching entries are still recorded,
+    /// but the UI gets them with the content hidden until revealed.
+    pub mask_patterns: Vec<String>,
+    /// Color scheme of the history window
+    pub theme: Theme,
+    /// History window width in logical points; unset keeps the current width
+    pub window_width: Option<u32>,
+    /// History window height in logical points; unset keeps the current height
+    pub window_height: Option<u32>,
+    /// Where the history window appears when summoned
+    pub popup_position: PopupPosition,
+    /// Cap on the history list height in pixels. When set, the window shrinks
+    /// to fit short histories and longer ones scroll; unset fills the window.
+    pub list_max_height: Option<u32>,
+    /// Denser history rows with smaller text, to fit more entries
+    pub compact: bool,
+    /// CSS font family of the history rows, e.g. `Menlo, monospace`; unset
+    /// keeps the system font
+    pub popup_font_family: Option<String>,
+    /// Font size of the history rows in pixels; unset keeps the default
+    pub popup_font_size: Option<u32>,
+    /// Size the window to its widest row and the list's height, within
+    /// bounds, instead of keeping a fixed size
+    pub autosize_popup: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            normalize_line_endings: true,
+            trim_on_capture: false,
+            log_to_file: false,
+            slot_shortcuts_enabled: false,
+            collapse_whitespace_dupes: false,
+            case_insensitive_dedup: false,
+            copy_nth_enabled: false,
+            copy_nth_offset: 1,
+            copy_nth_paste: false,
+            toggle_monitoring_enabled: false,
+            toggle_monitoring_shortcut: "Ctrl+Option+M".to_string(),
+            paste_as_typing: false,
+            always_on_top: false,
+            hide_on_blur: true,
+            steal_focus_on_show: true,
+            close_after_copy: true,
+            start_hidden: true,
+            profile: String::new(),
+            notify_on_capture: false,
+            fuzzy_search: false,
+            trigger_mode: TriggerMode::default(),
+            debounce_ms: 150,
+            collapse_consecutive_same_source_ms: 0,
+            emit_interval_ms: 200,
+            min_entry_chars: 0,
+            record_file_copies: false,
+            capture_primary_selection: false,
+            first_run_capture: true,
+            excluded_apps: Vec::new(),
+            included_apps: Vec::new(),
+            pause_when_locked: false,
+            trash_retention_days: 7,
+            max_pinned: 20,
+            unpin_oldest_when_full: false,
+            eviction_policy: EvictionPolicy::default(),
+            mask_patterns: Vec::new(),
+            theme: Theme::default(),
+            window_width: None,
+            window_height: None,
+            popup_position: PopupPosition::default(),
+            list_max_height: None,
+            compact: false,
+            popup_font_family: None,
+            popup_font_size: None,
+            autosize_popup: false,
+        }
+    }
+}
+
+static SETTINGS: Mutex<Option<Settings>> = Mutex::new(None);
+
+pub fn get_settings_path() -> PathBuf {
+    get_data_dir().join("settings.json")
+}
+
+fn load_settings() -> Settings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the active settings, reading `settings.json` on first use.
+pub fn current_settings() -> Settings {
+    SETTINGS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(load_settings)
+        .clone()
+}
+
+/// Re-reads `settings.json`, e.g. after it was edited by hand. Unlike the
+/// first load, a file that doesn't parse is an error and the active settings
+/// stay as they are.
+pub fn reload_settings() -> Result<Settings, String> {
+    let settings = match fs::read_to_string(get_settings_path()) {
+        Ok(json) => {
+            serde_json::from_str(&json).map_err(|e| format!("Invalid settings.json: {}", e))?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Settings::default(),
+        Err(e) => return Err(e.to_string()),
+    };
+    *SETTINGS.lock().unwrap() = Some(settings.clone());
+    Ok(settings)
+}
+
+/// Makes `settings` the active settings without writing them, for callers
+/// that wrote `settings.json` themselves
+pub fn set_current_settings(settings: Settings) {
+    *SETTINGS.lock().unwrap() = Some(settings);
+}
+
+/// Persists `settings` and makes them the active settings.
+pub fn save_settings(settings: Settings) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(&settings)?;
+    let _data = DATA_LOCK.lock().unwrap();
+    fs::write(get_settings_path(), json)?;
+    remember_own_write(&get_settings_path());
+    *SETTINGS.lock().unwrap() = Some(settings);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+/// A key combination such as `Ctrl+Option+M`, matched against key events
+// Only matched against key events on macOS; elsewhere it's just validated
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shortcut {
+    pub shift: bool,
+    pub control: bool,
+    pub option: bool,
+    pub command: bool,
+    /// macOS virtual key code of the non-modifier key
+    pub key_code: u16,
+}
+
+/// Virtual key codes of the letter and digit keys (kVK_ANSI_*)
+const KEY_CODES: [(char, u16); 36] = [
+    ('a', 0x00),
+    ('s', 0x01),
+    ('d', 0x02),
+    ('f', 0x03),
+    ('h', 0x04),
+    ('g', 0x05),
+    ('z', 0x06),
+    ('x', 0x07),
+    ('c', 0x08),
+    ('v', 0x09),
+    ('b', 0x0B),
+    ('q', 0x0C),
+    ('w', 0x0D),
+    ('e', 0x0E),
+    ('r', 0x0F),
+    ('y', 0x10),
+    ('t', 0x11),
+    ('1', 0x12),
+    ('2', 0x13),
+    ('3', 0x14),
+    ('4', 0x15),
+    ('6', 0x16),
+    ('5', 0x17),
+    ('9', 0x19),
+    ('7', 0x1A),
+    ('8', 0x1C),
+    ('0', 0x1D),
+    ('o', 0x1F),
+    ('u', 0x20),
+    ('i', 0x22),
+    ('p', 0x23),
+    ('l', 0x25),
+    ('j', 0x26),
+    ('k', 0x28),
+    ('n', 0x2D),
+    ('m', 0x2E),
+];
+
+impl Shortcut {
+    /// Parses `+`-separated modifiers followed by a letter or digit, e.g.
+    /// `Ctrl+Option+M` or `cmd+shift+0`. Ctrl, Option or Cmd is required, so
+    /// the shortcut can't fire while typing.
+    pub fn parse(spec: &str) -> Result<Shortcut, String> {
+        let invalid = || format!("Invalid shortcut: {}", spec);
+        let mut parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+        let key = parts.pop().ok_or_else(invalid)?;
+
+        let mut shortcut = Shortcut {
+            shift: false,
+            control: false,
+            option: false,
+            command: false,
+            key_code: 0,
+        };
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "shift" => shortcut.shift = true,
+                "ctrl" | "control" => shortcut.control = true,
+                "opt" | "option" | "alt" => shortcut.option = true,
+                "cmd" | "command" => shortcut.command = true,
+                _ => return Err(invalid()),
+            }
+        }
+        if !(shortcut.control || shortcut.option || shortcut.command) {
+            return Err(format!("Shortcut needs Ctrl, Option or Cmd: {}", spec));
+        }
+
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) => c.to_ascii_lowercase(),
+            _ => return Err(invalid()),
+        };
+        shortcut.key_code = KEY_CODES
+            .iter()
+            .find(|(c, _)| *c == key)
+            .map(|(_, code)| *code)
+            .ok_or_else(invalid)?;
+        Ok(shortcut)
+    }
+}
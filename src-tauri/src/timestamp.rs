@@ -0,0 +1,33 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Writes a local timestamp as UTC RFC 3339 (`...Z`), so stored history reads
+/// the same in any timezone. Use with `#[serde(with = "timestamp")]`.
+pub fn serialize<S: Serializer>(time: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error> {
+    time.with_timezone(&Utc).serialize(serializer)
+}
+
+/// Reads an RFC 3339 timestamp with any offset and converts it to local time
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Local>, D::Error> {
+    DateTime::<Local>::deserialize(deserializer)
+}
+
+/// The same for optional timestamps: `#[serde(with = "timestamp::option")]`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Local>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.map(|t| t.with_timezone(&Utc)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Local>>, D::Error> {
+        Option::<DateTime<Local>>::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::settings::current_settings;
+use crate::{profile_data_path, read_entries, write_entries, ClipboardEntry, DATA_LOCK};
+
+/// An entry removed by `delete_entry`, kept until the retention period ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    #[serde(flatten)]
+    pub entry: ClipboardEntry,
+    #[serde(with = "crate::timestamp")]
+    pub deleted_at: DateTime<Local>,
+}
+
+fn get_trash_path() -> PathBuf {
+    profile_data_path("trash")
+}
+
+/// Trash of the active profile, oldest deletion first. Entries older than
+/// `trash_retention_days` are purged on every load.
+pub fn load_trash() -> Vec<TrashedEntry> {
+    let mut trash: Vec<TrashedEntry> = read_entries(&get_trash_path());
+    let retention_days = current_settings().trash_retention_days;
+    if purge_expired(&mut trash, retention_days, Local::now()) {
+        if let Err(e) = save_trash(&trash) {
+            log::error!("Failed to purge trash: {}", e);
+        }
+    }
+    trash
+}
+
+/// Drops entries deleted more than `retention_days` before `now`. Returns
+/// whether any were dropped.
+fn purge_expired(trash: &mut Vec<TrashedEntry>, retention_days: u32, now: DateTime<Local>) -> bool {
+    let cutoff = now - Duration::days(retention_days.into());
+    let before = trash.len();
+    trash.retain(|t| t.deleted_at >= cutoff);
+    trash.len() != before
+}
+
+pub fn move_to_trash(entries: Vec<ClipboardEntry>) -> std::io::Result<()> {
+    let mut trash = load_trash();
+    let deleted_at = Local::now();
+    trash.extend(
+        entries
+            .into_iter()
+            .map(|entry| TrashedEntry { entry, deleted_at }),
+    );
+    save_trash(&trash)
+}
+
+fn save_trash(trash: &[TrashedEntry]) -> std::io::Result<()> {
+    let _data = DATA_LOCK.lock().unwrap();
+    write_entries(&get_trash_path(), trash)
+}
+
+/// Removes the entries with the given ids from the trash and returns them
+pub fn take_from_trash(ids: &[String]) -> std::io::Result<Vec<ClipboardEntry>> {
+    let (taken, kept) = split_by_id(load_trash(), ids);
+    if !taken.is_empty() {
+        save_trash(&kept)?;
+    }
+    Ok(taken)
+}
+
+/// Separates the entries with the given ids (returned first) from the rest
+fn split_by_id(
+    trash: Vec<TrashedEntry>,
+    ids: &[String],
+) -> (Vec<ClipboardEntry>, Vec<TrashedEntry>) {
+    let (taken, kept): (Vec<_>, Vec<_>) =
+        trash.into_iter().partition(|t| ids.contains(&t.entry.id));
+    (taken.into_iter().map(|t| t.entry).collect(), kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trashed(content: &str, deleted_at: DateTime<Local>) -> TrashedEntry {
+        TrashedEntry {
+            entry: ClipboardEntry::new(content.to_string()),
+            deleted_at,
+        }
+    }
+
+    #[test]
+    fn purges_entries_past_retention() {
+        let now = Local::now();
+        let mut trash = vec![
+            trashed("expired", now - Duration::days(8)),
+            trashed("recent", now - Duration::days(6)),
+        ];
+
+        assert!(purge_expired(&mut trash, 7, now));
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].entry.content, "recent");
+    }
+
+    #[test]
+    fn keeps_entries_at_the_cutoff() {
+        let now = Local::now();
+        let mut trash = vec![trashed("boundary", now - Duration::days(7))];
+        assert!(!purge_expired(&mut trash, 7, now));
+        assert_eq!(trash.len(), 1);
+    }
+
+    #[test]
+    fn takes_only_the_requested_entries() {
+        let now = Local::now();
+        let trash = vec![trashed("a", now), trashed("b", now), trashed("c", now)];
+        let ids = vec![trash[0].entry.id.clone(), trash[2].entry.id.clone()];
+
+        let (taken, kept) = split_by_id(trash, &ids);
+        let taken: Vec<_> = taken.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(taken, ["a", "c"]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].entry.content, "b");
+    }
+
+    #[test]
+    fn taking_unknown_ids_takes_nothing() {
+        let trash = vec![trashed("a", Local::now())];
+        let (taken, kept) = split_by_id(trash, &["missing".to_string()]);
+        assert!(taken.is_empty());
+        assert_eq!(kept.len(), 1);
+    }
+}
@@ -1,14 +1,55 @@
-import { useEffect, useState, useRef, useCallback, useMemo } from "react";
+import {
+  Fragment,
+  useEffect,
+  useState,
+  useRef,
+  useCallback,
+  useMemo,
+} from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
+import { LogicalSize } from "@tauri-apps/api/dpi";
 import { getVersion } from "@tauri-apps/api/app";
-import { Monitor, Sun, Moon, Trash2 } from "lucide-react";
+import {
+  Monitor,
+  Sun,
+  Moon,
+  Trash2,
+  Pin,
+  ChevronDown,
+  ChevronUp,
+  FolderOpen,
+  X,
+  Star,
+  Eye,
+  Braces,
+  StickyNote,
+} from "lucide-react";
+
+type ContentKind = "Text" | "Url" | "Code" | "Files";
 
 interface ClipboardEntry {
+  id: string;
   timestamp: string;
   content: string;
   pinned: boolean;
+  kind: ContentKind;
+  html?: string;
+  manual: boolean;
+  source_app?: string;
+  pinned_at?: string;
+  last_used?: string;
+  label?: string;
+  note?: string;
+  // Content was hidden by a mask_patterns setting; use reveal_entry
+  masked?: boolean;
+}
+
+interface SearchResult {
+  entry: ClipboardEntry;
+  score: number;
+  indices: number[];
 }
 
 interface HistoryResponse {
@@ -16,12 +57,173 @@ interface HistoryResponse {
   max_entries: number;
 }
 
-type Theme = "system" | "light" | "dark";
+type Theme = "auto" | "light" | "dark";
+
+// Subset of the Rust Settings struct used by the UI
+interface Settings {
+  always_on_top: boolean;
+  fuzzy_search: boolean;
+  theme: Theme;
+  list_max_height: number | null;
+  autosize_popup: boolean;
+  close_after_copy: boolean;
+  compact: boolean;
+  popup_font_family: string | null;
+  popup_font_size: number | null;
+}
+
+type Transform =
+  | "Base64Encode"
+  | "Base64Decode"
+  | "UrlEncode"
+  | "UrlDecode"
+  | "PrettyJson";
+
+// Entries of the right-click context menu, in display order (src/App.tsx)
+const TRANSFORMS: { transform: Transform; label: string }[] = [
+  { transform: "Base64Encode", label: "Copy as Base64" },
+  { transform: "Base64Decode", label: "Copy Base64-decoded" },
+  { transform: "UrlEncode", label: "Copy URL-encoded" },
+  { transform: "UrlDecode", label: "Copy URL-decoded" },
+  { transform: "PrettyJson", label: "Copy as pretty JSON" },
+];
+
+// Whether content is a JSON object or array, i.e. worth pretty-printing
+// (src/App.tsx). Bare JSON scalars like "42" don't count.
+const isJsonContainer = (content: string) => {
+  if (!/^\s*[{[]/.test(content)) return false;
+  try {
+    JSON.parse(content);
+    return true;
+  } catch {
+    return false;
+  }
+};
+
+interface ContextMenuState {
+  x: number;
+  y: number;
+  index: number;
+  entry: ClipboardEntry;
+}
+
+// Local calendar day of a date as YYYY-MM-DD (src/App.tsx)
+const dayKey = (date: Date) =>
+  `${date.getFullYear()}-${String(date.getMonth() + 1).padStart(2, "0")}-${String(date.getDate()).padStart(2, "0")}`;
+
+// Label for the date header above a group of entries (src/App.tsx):
+// "Today", "Yesterday", or the YYYY-MM-DD date for older days.
+const dayLabel = (timestamp: string) => {
+  const key = dayKey(new Date(timestamp));
+  const today = new Date();
+  const yesterday = new Date();
+  yesterday.setDate(today.getDate() - 1);
+  if (key === dayKey(today)) return "Today";
+  if (key === dayKey(yesterday)) return "Yesterday";
+  return key;
+};
+
+// Code-point indices of every case-insensitive occurrence of each (already
+// lowercased) search term in the content (src/App.tsx)
+const substringMatchIndices = (content: string, terms: string[]) => {
+  const chars = Array.from(content).map((c) => c.toLowerCase());
+  const indices = new Set<number>();
+  for (const term of terms) {
+    const termChars = Array.from(term);
+    for (let i = 0; i + termChars.length <= chars.length; i++) {
+      if (termChars.every((c, j) => chars[i + j] === c)) {
+        termChars.forEach((_, j) => indices.add(i + j));
+      }
+    }
+  }
+  return indices;
+};
+
+// Renders text with the characters at the given code-point indices wrapped
+// in <mark> (src/App.tsx). React escapes the text, so no manual escaping.
+const HighlightedText = ({
+  text,
+  indices,
+}: {
+  text: string;
+  indices?: Set<number>;
+}) => {
+  if (!indices || indices.size === 0) return <>{text}</>;
+  const segments: { text: string; matched: boolean }[] = [];
+  Array.from(text).forEach((char, i) => {
+    const matched = indices.has(i);
+    const last = segments[segments.length - 1];
+    if (last && last.matched === matched) {
+      last.text += char;
+    } else {
+      segments.push({ text: char, matched });
+    }
+  });
+  return (
+    <>
+      {segments.map((segment, i) =>
+        segment.matched ? (
+          <mark key={i} className="match">
+            {segment.text}
+          </mark>
+        ) : (
+          <Fragment key={i}>{segment.text}</Fragment>
+        )
+      )}
+    </>
+  );
+};
+
+// Abbreviates large counts as "1.2k" (src/App.tsx)
+const formatCount = (n: number) =>
+  n >= 1000 ? `${(n / 1000).toFixed(1).replace(/\.0$/, "")}k` : `${n}`;
+
+// Character/word count badge text for an entry (src/App.tsx)
+const lengthBadge = (content: string) => {
+  const chars = [...content].length;
+  const words = content.split(/\s+/).filter(Boolean).length;
+  return `${formatCount(chars)} chars · ${formatCount(words)} words`;
+};
+
+// Longest text shown in the hover tooltip; every row renders one, so huge
+// entries are cut off instead of bloating the list (src/App.tsx)
+const TOOLTIP_MAX_CHARS = 2000;
+
+const tooltipText = (content: string) =>
+  content.length > TOOLTIP_MAX_CHARS
+    ? `${content.slice(0, TOOLTIP_MAX_CHARS)}…`
+    : content;
+
+// Window size bounds for autosize_popup (src/App.tsx). The minimum matches
+// the backend's MIN_WINDOW_WIDTH / MIN_WINDOW_HEIGHT.
+const AUTOSIZE_MIN_WIDTH = 300;
+const AUTOSIZE_MAX_WIDTH = 800;
+const AUTOSIZE_MIN_HEIGHT = 200;
+const AUTOSIZE_MAX_HEIGHT = 800;
+
+const clamp = (value: number, min: number, max: number) =>
+  Math.min(Math.max(value, min), max);
+
+// Width a history row needs to show its content without truncation
+// (src/App.tsx). The length badge's auto margin soaks up spare room in wide
+// rows, so that space is taken off.
+const rowNaturalWidth = (row: HTMLElement) => {
+  const content = row.querySelector<HTMLElement>(".history-content");
+  const next = content?.nextElementSibling as HTMLElement | null | undefined;
+  if (!content || !next) return row.offsetWidth;
+  const gap = parseFloat(getComputedStyle(row).columnGap) || 0;
+  const spare = next.offsetLeft - (content.offsetLeft + content.offsetWidth) - gap;
+  return (
+    row.offsetWidth -
+    Math.max(spare, 0) +
+    (content.scrollWidth - content.clientWidth)
+  );
+};
 
 const ThemeIcon = ({ theme }: { theme: Theme }) => {
   const iconProps = { size: 16, strokeWidth: 2 };
   switch (theme) {
-    case "system":
+    case "auto":
       return <Monitor {...iconProps} />;
     case "light":
       return <Sun {...iconProps} />;
@@ -38,39 +240,245 @@ function App() {
   const [query, setQuery] = useState<string>("");
   const [version, setVersion] = useState<string>("");
   const [showClearConfirm, setShowClearConfirm] = useState<boolean>(false);
-  const [theme, setTheme] = useState<Theme>(() => {
-    return (localStorage.getItem("theme") as Theme) || "system";
-  });
+  const [alwaysOnTop, setAlwaysOnTop] = useState<boolean>(false);
+  const [expandedIds, setExpandedIds] = useState<Set<string>>(new Set());
+  const [undoCount, setUndoCount] = useState<number | null>(null);
+  const [fuzzy, setFuzzy] = useState<boolean>(false);
+  const [fuzzyResults, setFuzzyResults] = useState<SearchResult[] | null>(
+    null
+  );
+  const [contextMenu, setContextMenu] = useState<ContextMenuState | null>(null);
+  const [transformError, setTransformError] = useState<string | null>(null);
+  const [theme, setTheme] = useState<Theme>("dark");
+  const [listMaxHeight, setListMaxHeight] = useState<number | null>(null);
+  const [compact, setCompact] = useState<boolean>(false);
+  const [fontFamily, setFontFamily] = useState<string | null>(null);
+  const [fontSize, setFontSize] = useState<number | null>(null);
+  const [autosize, setAutosize] = useState<boolean>(false);
+  // Bumped when the backend asks to fit the window before showing it; the
+  // autosize effect then shows it once resized
+  const [fitRequest, setFitRequest] = useState(0);
+  const showAfterFit = useRef(false);
+  const [closeAfterCopy, setCloseAfterCopy] = useState<boolean>(true);
+  // Pretty-printed content of JSON entries shown formatted, by id
+  const [prettyJson, setPrettyJson] = useState<Map<string, string>>(new Map());
+  // Real content of masked entries the user chose to reveal, by id
+  const [revealed, setRevealed] = useState<Map<string, string>>(new Map());
   const listRef = useRef<HTMLDivElement>(null);
   const itemRefs = useRef<(HTMLDivElement | null)[]>([]);
   const searchInputRef = useRef<HTMLInputElement>(null);
 
+  // In fuzzy mode the ranking comes from the backend's search_history
+  // (src/App.tsx). Results are re-fetched whenever the query or history changes.
+  useEffect(() => {
+    if (!fuzzy || !query.trim()) {
+      setFuzzyResults(null);
+      return;
+    }
+    let cancelled = false;
+    invoke<SearchResult[]>("search_history", { query, fuzzy: true })
+      .then((results) => {
+        if (!cancelled) setFuzzyResults(results);
+      })
+      .catch((error) => console.error("Failed to search:", error));
+    return () => {
+      cancelled = true;
+    };
+  }, [fuzzy, query, history]);
+
   // Filters history by case-insensitive AND match on content (src/App.tsx).
   // The query is split on whitespace and every term must be a substring of the
   // entry content, so space-separated words act as an AND search.
+  // In fuzzy mode the backend's ranked results are used instead.
   const filteredHistory = useMemo(() => {
+    if (fuzzyResults) return fuzzyResults.map((result) => result.entry);
     const terms = query.toLowerCase().trim().split(/\s+/).filter(Boolean);
     if (terms.length === 0) return history;
     return history.filter((entry) => {
       const content = entry.content.toLowerCase();
       return terms.every((term) => content.includes(term));
     });
-  }, [history, query]);
+  }, [history, query, fuzzyResults]);
+
+  // Ids of entries holding a JSON object or array (src/App.tsx)
+  const jsonIds = useMemo(
+    () =>
+      new Set(
+        filteredHistory
+          .filter((entry) => isJsonContainer(entry.content))
+          .map((entry) => entry.id)
+      ),
+    [filteredHistory]
+  );
 
+  // Matched character positions per entry id, for highlighting (src/App.tsx).
+  // Fuzzy mode uses the indices reported by the matcher.
+  const matchIndices = useMemo(() => {
+    const map = new Map<string, Set<number>>();
+    if (fuzzyResults) {
+      fuzzyResults.forEach((result) =>
+        map.set(result.entry.id, new Set(result.indices))
+      );
+      return map;
+    }
+    const terms = query.toLowerCase().trim().split(/\s+/).filter(Boolean);
+    if (terms.length === 0) return map;
+    filteredHistory.forEach((entry) =>
+      map.set(entry.id, substringMatchIndices(entry.content, terms))
+    );
+    return map;
+  }, [filteredHistory, fuzzyResults, query]);
+
+  // With a capped list height, fit the window to its content so short
+  // histories don't leave a large empty window (src/App.tsx)
+  useEffect(() => {
+    if (autosize || listMaxHeight === null || !listRef.current) return;
+    const listBottom = listRef.current.getBoundingClientRect().bottom;
+    const padding = 8; // .app bottom padding
+    getCurrentWindow()
+      .setSize(new LogicalSize(window.innerWidth, Math.ceil(listBottom) + padding))
+      .catch((error) => console.error("Failed to resize window:", error));
+  }, [autosize, listMaxHeight, filteredHistory]);
+
+  // With autosize_popup, fit the window to the widest row and to the list's
+  // content height, within bounds, so short clips get a compact window
+  // (src/App.tsx)
   useEffect(() => {
-    localStorage.setItem("theme", theme);
-    if (theme === "system") {
+    const list = listRef.current;
+    if (!autosize) return;
+    if (!list) {
+      // Nothing to measure, e.g. an empty history; show it as it is
+      if (showAfterFit.current) {
+        showAfterFit.current = false;
+        invoke("show_popup");
+      }
+      return;
+    }
+    const rows = itemRefs.current
+      .slice(0, filteredHistory.length)
+      .filter((row): row is HTMLDivElement => row !== null);
+    const widest = Math.max(0, ...rows.map(rowNaturalWidth));
+    const width = clamp(
+      Math.ceil(window.innerWidth - list.clientWidth + widest),
+      AUTOSIZE_MIN_WIDTH,
+      AUTOSIZE_MAX_WIDTH
+    );
+    const height = clamp(
+      Math.ceil(window.innerHeight - list.clientHeight + list.scrollHeight),
+      AUTOSIZE_MIN_HEIGHT,
+      AUTOSIZE_MAX_HEIGHT
+    );
+    const resized = getCurrentWindow()
+      .setSize(new LogicalSize(width, height))
+      .catch((error) => console.error("Failed to resize window:", error));
+    if (showAfterFit.current) {
+      showAfterFit.current = false;
+      resized.then(() => invoke("show_popup"));
+    }
+  }, [autosize, filteredHistory, compact, fitRequest]);
+
+  // "auto" leaves the scheme to prefers-color-scheme (src/styles.css)
+  useEffect(() => {
+    if (theme === "auto") {
       document.body.removeAttribute("data-theme");
     } else {
       document.body.setAttribute("data-theme", theme);
     }
   }, [theme]);
 
-  const cycleTheme = () => {
-    const themes: Theme[] = ["system", "light", "dark"];
+  // Unset font settings fall back to the stylesheet's fonts (src/styles.css)
+  useEffect(() => {
+    const style = document.body.style;
+    if (fontFamily) {
+      style.setProperty("--entry-font-family", fontFamily);
+    } else {
+      style.removeProperty("--entry-font-family");
+    }
+    if (fontSize !== null) {
+      style.setProperty("--entry-font-size", `${fontSize}px`);
+    } else {
+      style.removeProperty("--entry-font-size");
+    }
+  }, [fontFamily, fontSize]);
+
+  // Cycles auto -> light -> dark and persists the choice (src/App.tsx)
+  const cycleTheme = async () => {
+    const themes: Theme[] = ["auto", "light", "dark"];
     const currentIndex = themes.indexOf(theme);
-    const nextIndex = (currentIndex + 1) % themes.length;
-    setTheme(themes[nextIndex]);
+    const next = themes[(currentIndex + 1) % themes.length];
+    setTheme(next);
+    try {
+      await updateSetting("theme", next);
+    } catch (error) {
+      console.error("Failed to save theme setting:", error);
+    }
+  };
+
+  // Toggles the "keep window on top" setting (src/App.tsx). The backend
+  // persists it and applies it to the window, returning the new state.
+  const toggleAlwaysOnTop = async () => {
+    try {
+      setAlwaysOnTop(await invoke<boolean>("toggle_always_on_top"));
+    } catch (error) {
+      console.error("Failed to toggle always on top:", error);
+    }
+  };
+
+  const openDataDir = async () => {
+    try {
+      await invoke("open_data_dir");
+    } catch (error) {
+      console.error("Failed to open data folder:", error);
+    }
+  };
+
+  // Moves the theme older versions kept in localStorage ("system", "light"
+  // or "dark") into settings, once (src/App.tsx)
+  const migrateLegacyTheme = async (settings: Settings) => {
+    const legacy = localStorage.getItem("theme");
+    if (legacy === null) return settings;
+    localStorage.removeItem("theme");
+    const theme: Theme =
+      legacy === "light" || legacy === "dark" ? legacy : "auto";
+    try {
+      await updateSetting("theme", theme);
+    } catch (error) {
+      console.error("Failed to migrate theme setting:", error);
+      return settings;
+    }
+    return { ...settings, theme };
+  };
+
+  // Updates a single setting (src/App.tsx). The full settings object is read
+  // back first so fields the UI doesn't know about are preserved.
+  const updateSetting = async <K extends keyof Settings>(
+    key: K,
+    value: Settings[K]
+  ) => {
+    const settings = await invoke<Settings>("get_settings");
+    await invoke("update_settings", { settings: { ...settings, [key]: value } });
+  };
+
+  // Toggles fuzzy search mode and persists the choice (src/App.tsx)
+  const toggleFuzzy = async () => {
+    const next = !fuzzy;
+    setFuzzy(next);
+    try {
+      await updateSetting("fuzzy_search", next);
+    } catch (error) {
+      console.error("Failed to save fuzzy search setting:", error);
+    }
+  };
+
+  // Toggles compact (denser) rows and persists the choice (src/App.tsx)
+  const toggleCompact = async () => {
+    const next = !compact;
+    setCompact(next);
+    try {
+      await updateSetting("compact", next);
+    } catch (error) {
+      console.error("Failed to save compact setting:", error);
+    }
   };
 
   const loadHistory = async () => {
@@ -106,6 +514,16 @@ function App() {
         return;
       }
 
+      // While the transform context menu is open (src/App.tsx), Escape closes
+      // it and list navigation is suppressed.
+      if (contextMenu) {
+        if (e.key === "Escape") {
+          e.preventDefault();
+          closeContextMenu();
+        }
+        return;
+      }
+
       // Ignore keystrokes emitted while an IME composition is active (src/App.tsx).
       // When converting Japanese with the IME, the Enter that confirms the
       // conversion would otherwise be treated as "copy & close". keyCode 229 is
@@ -116,6 +534,14 @@ function App() {
       // search box, j/k should be entered as text instead of navigating.
       const inSearch = document.activeElement === searchInputRef.current;
 
+      // Escape with nothing left to cancel closes the window (src/App.tsx).
+      // The search input handles it first while it still has a query.
+      if (e.key === "Escape") {
+        e.preventDefault();
+        dismissWindow();
+        return;
+      }
+
       if (filteredHistory.length === 0) return;
 
       switch (e.key) {
@@ -156,12 +582,19 @@ function App() {
         case "Enter":
           e.preventDefault();
           if (filteredHistory[selectedIndex]) {
-            handleCopy(filteredHistory[selectedIndex].content, selectedIndex);
+            handleCopy(filteredHistory[selectedIndex], selectedIndex);
           }
           break;
       }
     },
-    [filteredHistory, selectedIndex, scrollToSelected, showClearConfirm]
+    [
+      filteredHistory,
+      selectedIndex,
+      scrollToSelected,
+      showClearConfirm,
+      contextMenu,
+      closeAfterCopy,
+    ]
   );
 
   useEffect(() => {
@@ -178,9 +611,36 @@ function App() {
     );
   }, [filteredHistory.length]);
 
+  // Hide the undo toast a few seconds after a delete/clear (src/App.tsx)
+  useEffect(() => {
+    if (undoCount === null) return;
+    const timer = setTimeout(() => setUndoCount(null), 5000);
+    return () => clearTimeout(timer);
+  }, [undoCount]);
+
   useEffect(() => {
     loadHistory();
     getVersion().then(setVersion);
+    const applySettings = (settings: Settings) => {
+      setAlwaysOnTop(settings.always_on_top);
+      setFuzzy(settings.fuzzy_search);
+      setTheme(settings.theme);
+      setListMaxHeight(settings.list_max_height);
+      setCompact(settings.compact);
+      setFontFamily(settings.popup_font_family);
+      setFontSize(settings.popup_font_size);
+      setAutosize(settings.autosize_popup);
+      setCloseAfterCopy(settings.close_after_copy);
+    };
+    invoke<Settings>("get_settings")
+      .then(migrateLegacyTheme)
+      .then(applySettings);
+
+    // Emitted when settings.json was reloaded from disk, e.g. after a hand
+    // edit (src/App.tsx)
+    const unlistenSettings = listen<Settings>("settings-updated", (event) =>
+      applySettings(event.payload)
+    );
 
     const unlistenChanged = listen<ClipboardEntry>("clipboard-changed", () => {
       loadHistory();
@@ -192,29 +652,108 @@ function App() {
     const unlistenShow = listen("show-window-at-mouse", () => {
       setQuery("");
       setSelectedIndex(0);
+      setRevealed(new Map());
       requestAnimationFrame(() => searchInputRef.current?.focus());
     });
 
+    // With autosize_popup the backend leaves the window hidden until it has
+    // been fitted to the freshly loaded history (src/App.tsx)
+    const unlistenFit = listen("fit-popup", async () => {
+      await loadHistory();
+      showAfterFit.current = true;
+      setFitRequest((n) => n + 1);
+    });
+
+    // The show event can arrive before the window is actually key, in which
+    // case the focus call above is lost and keystrokes go nowhere until the
+    // user clicks. Focusing again once the window gains focus fixes that.
+    const unlistenFocus = getCurrentWindow().onFocusChanged(({ payload }) => {
+      if (payload && document.activeElement !== searchInputRef.current) {
+        searchInputRef.current?.focus();
+      }
+    });
+
+    // The backend emits undo-available with the number of removed entries
+    // after a delete/clear, so the undo toast can be offered (src/App.tsx)
+    const unlistenUndo = listen<number>("undo-available", (event) => {
+      setUndoCount(event.payload);
+    });
+
+    // Emitted when the history changed without a new capture, e.g. after a
+    // profile switch (src/App.tsx)
+    const unlistenUpdated = listen("history-updated", () => {
+      loadHistory();
+      setSelectedIndex(0);
+    });
+
     return () => {
       unlistenChanged.then((f) => f());
+      unlistenUpdated.then((f) => f());
+      unlistenSettings.then((f) => f());
       unlistenShow.then((f) => f());
+      unlistenFit.then((f) => f());
+      unlistenFocus.then((f) => f());
+      unlistenUndo.then((f) => f());
     };
   }, []);
 
-  const handleCopy = async (content: string, index: number) => {
+  // Hides the window without copying and hands focus back to the previous
+  // application (src/App.tsx)
+  const dismissWindow = async () => {
+    closeContextMenu();
+    await getCurrentWindow().hide();
+    await invoke("restore_previous_app");
+  };
+
+  const handleCopy = async (
+    entry: ClipboardEntry,
+    index: number,
+    transform?: Transform,
+    asHtml?: boolean
+  ) => {
     try {
-      await invoke("copy_to_clipboard", { content });
+      // Masked entries only carry placeholder text; copy the real content
+      const content = entry.masked
+        ? await invoke<string>("reveal_entry", { id: entry.id })
+        : entry.content;
+      await invoke("copy_to_clipboard", { content, transform, asHtml });
+      closeContextMenu();
       setCopiedIndex(index);
-      // Close window after copy
-      await getCurrentWindow().hide();
-      // Restore focus to the previous application
-      await invoke("restore_previous_app");
+      // Close window after copy, unless close_after_copy is off so several
+      // entries can be copied in a row
+      if (closeAfterCopy) {
+        await getCurrentWindow().hide();
+        // Restore focus to the previous application
+        await invoke("restore_previous_app");
+      }
       setTimeout(() => setCopiedIndex(null), 1500);
     } catch (error) {
-      console.error("Failed to copy:", error);
+      // Transform failures (e.g. invalid base64) are shown in the context menu
+      if (transform) {
+        setTransformError(String(error));
+      } else {
+        console.error("Failed to copy:", error);
+      }
     }
   };
 
+  // Opens the transform context menu for an entry at the cursor (src/App.tsx)
+  const handleContextMenu = (
+    e: React.MouseEvent,
+    entry: ClipboardEntry,
+    index: number
+  ) => {
+    e.preventDefault();
+    setSelectedIndex(index);
+    setTransformError(null);
+    setContextMenu({ x: e.clientX, y: e.clientY, index, entry });
+  };
+
+  const closeContextMenu = () => {
+    setContextMenu(null);
+    setTransformError(null);
+  };
+
   const handleTogglePin = async (
     e: React.MouseEvent,
     timestamp: string,
@@ -229,6 +768,68 @@ function App() {
     }
   };
 
+  // Deletes a single entry (src/App.tsx). Stops propagation so the click
+  // doesn't also copy the entry; the window stays open.
+  const handleDelete = async (e: React.MouseEvent, id: string) => {
+    e.stopPropagation();
+    try {
+      await invoke("delete_entry", { id });
+      loadHistory();
+    } catch (error) {
+      console.error("Failed to delete entry:", error);
+    }
+  };
+
+  // Shows the real content of a masked entry in place (src/App.tsx). Stops
+  // propagation so the click doesn't also copy the entry.
+  const handleReveal = async (e: React.MouseEvent, id: string) => {
+    e.stopPropagation();
+    try {
+      const content = await invoke<string>("reveal_entry", { id });
+      setRevealed((prev) => new Map(prev).set(id, content));
+    } catch (error) {
+      console.error("Failed to reveal entry:", error);
+    }
+  };
+
+  // Shows/hides the pretty-printed view of a JSON entry (src/App.tsx). The
+  // stored content is left as copied. Stops propagation so the click doesn't
+  // also copy the entry.
+  const togglePrettyJson = async (e: React.MouseEvent, entry: ClipboardEntry) => {
+    e.stopPropagation();
+    if (prettyJson.has(entry.id)) {
+      setPrettyJson((prev) => {
+        const next = new Map(prev);
+        next.delete(entry.id);
+        return next;
+      });
+      return;
+    }
+    try {
+      const pretty = await invoke<string>("format_json", {
+        content: entry.content,
+      });
+      setPrettyJson((prev) => new Map(prev).set(entry.id, pretty));
+    } catch (error) {
+      console.error("Failed to format JSON:", error);
+    }
+  };
+
+  // Expands/collapses the full snippet view of a code entry (src/App.tsx).
+  // Stops propagation so the click doesn't also copy the entry.
+  const toggleExpanded = (e: React.MouseEvent, id: string) => {
+    e.stopPropagation();
+    setExpandedIds((prev) => {
+      const next = new Set(prev);
+      if (next.has(id)) {
+        next.delete(id);
+      } else {
+        next.add(id);
+      }
+      return next;
+    });
+  };
+
   // Emacs/readline-style line editing for the search input (src/App.tsx).
   // Ctrl+U clears to line start, Ctrl+W deletes the word before the cursor;
   // Escape clears the whole field. (Cmd is also accepted for U/W.)
@@ -289,19 +890,47 @@ function App() {
     }
   };
 
+  // Restores the entries removed by the last delete/clear (src/App.tsx)
+  const handleUndo = async () => {
+    try {
+      await invoke<number>("undo_last_removal");
+      loadHistory();
+    } catch (error) {
+      console.error("Failed to undo:", error);
+    } finally {
+      setUndoCount(null);
+    }
+  };
+
   // Cancels the clear-all confirmation dialog in src/App.tsx without deleting.
   const cancelClearAll = () => {
     setShowClearConfirm(false);
   };
 
   return (
-    <div className="app">
+    <div className={`app ${compact ? "compact" : ""}`}>
       <header className="header">
         <h1>Recall {version && <span className="version">v{version}</span>}</h1>
         <p className="subtitle">Clipboard History</p>
-        <button className="theme-toggle" onClick={cycleTheme} title={theme}>
-          <ThemeIcon theme={theme} />
-        </button>
+        <div className="header-actions">
+          <button
+            className={`window-pin-toggle ${alwaysOnTop ? "active" : ""}`}
+            onClick={toggleAlwaysOnTop}
+            title={alwaysOnTop ? "Unpin window" : "Keep window on top"}
+          >
+            <Pin size={16} strokeWidth={2} />
+          </button>
+          <button
+            className="data-dir-button"
+            onClick={openDataDir}
+            title="Open data folder"
+          >
+            <FolderOpen size={16} strokeWidth={2} />
+          </button>
+          <button className="theme-toggle" onClick={cycleTheme} title={theme}>
+            <ThemeIcon theme={theme} />
+          </button>
+        </div>
       </header>
 
       <div className="search-container">
@@ -319,6 +948,13 @@ function App() {
           autoCapitalize="off"
           spellCheck={false}
         />
+        <button
+          className={`fuzzy-toggle ${fuzzy ? "active" : ""}`}
+          onClick={toggleFuzzy}
+          title={fuzzy ? "Fuzzy search (on)" : "Fuzzy search (off)"}
+        >
+          Fuzzy
+        </button>
       </div>
 
       <div className="settings-row">
@@ -327,6 +963,13 @@ function App() {
             ? `${filteredHistory.length}件 / ${history.length}件`
             : `${history.length}/${maxEntries}件`}
         </span>
+        <button
+          className={`density-toggle ${compact ? "active" : ""}`}
+          onClick={toggleCompact}
+          title={compact ? "Switch to comfortable rows" : "Switch to compact rows"}
+        >
+          {compact ? "Compact" : "Comfortable"}
+        </button>
         <button
           className="clear-button"
           onClick={handleClearAll}
@@ -337,37 +980,182 @@ function App() {
         </button>
       </div>
 
-      <div className="history-list" ref={listRef}>
+      <div
+        className={`history-list ${listMaxHeight !== null ? "capped" : ""}`}
+        ref={listRef}
+        style={listMaxHeight !== null ? { maxHeight: listMaxHeight } : undefined}
+      >
         {filteredHistory.length === 0 ? (
           <div className="empty-state">
             {query.trim() ? "一致する履歴がありません" : "履歴がありません"}
           </div>
         ) : (
-          filteredHistory.map((entry, index) => (
-            <div
-              key={`${entry.timestamp}-${index}`}
-              ref={(el) => {
-                itemRefs.current[index] = el;
-              }}
-              className={`history-item ${copiedIndex === index ? "copied" : ""} ${selectedIndex === index ? "selected" : ""} ${entry.pinned ? "pinned" : ""}`}
-              onClick={() => handleCopy(entry.content, index)}
-              onMouseEnter={() => setSelectedIndex(index)}
-            >
-              <input
-                type="checkbox"
-                className="pin-checkbox"
-                checked={entry.pinned}
-                onClick={(e) => handleTogglePin(e, entry.timestamp, entry.pinned)}
-                onChange={() => {}}
-                title={entry.pinned ? "Unpin" : "Pin"}
-              />
-              <span className="history-content">{entry.content}</span>
-              <div className="history-tooltip">{entry.content}</div>
-            </div>
-          ))
+          filteredHistory.map((entry, index) => {
+            const isCode = entry.kind === "Code";
+            const pretty = prettyJson.get(entry.id);
+            const expanded =
+              (isCode && expandedIds.has(entry.id)) || pretty !== undefined;
+            const content = revealed.get(entry.id) ?? entry.content;
+            const hidden = entry.masked && !revealed.has(entry.id);
+            // Date headers separate days; they aren't part of the selectable list
+            const day = dayLabel(entry.timestamp);
+            const showHeader =
+              index === 0 ||
+              dayLabel(filteredHistory[index - 1].timestamp) !== day;
+            return (
+              <Fragment key={entry.id}>
+                {showHeader && <div className="date-header">{day}</div>}
+                <div
+                  ref={(el) => {
+                    itemRefs.current[index] = el;
+                  }}
+                  className={`history-item ${copiedIndex === index ? "copied" : ""} ${selectedIndex === index ? "selected" : ""} ${entry.pinned ? "pinned" : ""} ${entry.manual ? "manual" : ""} ${expanded ? "expanded" : ""}`}
+                  onClick={() => handleCopy(entry, index)}
+                  onContextMenu={(e) => handleContextMenu(e, entry, index)}
+                  onMouseEnter={() => setSelectedIndex(index)}
+                >
+                  <button
+                    className={`pin-star ${entry.pinned ? "active" : ""}`}
+                    onClick={(e) => handleTogglePin(e, entry.timestamp, entry.pinned)}
+                    title={entry.pinned ? "Unpin" : "Pin"}
+                  >
+                    <Star
+                      size={14}
+                      strokeWidth={2}
+                      fill={entry.pinned ? "currentColor" : "none"}
+                    />
+                  </button>
+                  <span className={`history-content ${isCode ? "code" : ""}`}>
+                    {entry.label ? (
+                      <span className="entry-label">{entry.label}</span>
+                    ) : (
+                      <HighlightedText
+                        text={content}
+                        indices={matchIndices.get(entry.id)}
+                      />
+                    )}
+                  </span>
+                  <span className="length-badge">
+                    {hidden ? "" : lengthBadge(content)}
+                  </span>
+                  {entry.note && (
+                    <span className="note-indicator" title={entry.note}>
+                      <StickyNote size={12} />
+                    </span>
+                  )}
+                  {hidden && (
+                    <button
+                      className="reveal-button"
+                      onClick={(e) => handleReveal(e, entry.id)}
+                      title="Reveal"
+                    >
+                      <Eye size={12} />
+                    </button>
+                  )}
+                  {jsonIds.has(entry.id) && (
+                    <button
+                      className={`json-toggle ${pretty !== undefined ? "active" : ""}`}
+                      onClick={(e) => togglePrettyJson(e, entry)}
+                      title={pretty !== undefined ? "Show as copied" : "Pretty-print JSON"}
+                    >
+                      <Braces size={12} />
+                    </button>
+                  )}
+                  {isCode && pretty === undefined && (
+                    <button
+                      className="expand-toggle"
+                      onClick={(e) => toggleExpanded(e, entry.id)}
+                      title={expanded ? "Collapse" : "Expand"}
+                    >
+                      {expanded ? <ChevronUp size={12} /> : <ChevronDown size={12} />}
+                    </button>
+                  )}
+                  <button
+                    className="delete-button"
+                    onClick={(e) => handleDelete(e, entry.id)}
+                    title="Delete"
+                  >
+                    <X size={12} />
+                  </button>
+                  {expanded && (
+                    <pre className="history-code-expanded">{pretty ?? content}</pre>
+                  )}
+                  <div className="history-tooltip">{tooltipText(content)}</div>
+                </div>
+              </Fragment>
+            );
+          })
         )}
       </div>
 
+      {undoCount !== null && (
+        <div className="undo-toast">
+          <span>
+            Deleted {undoCount} {undoCount === 1 ? "entry" : "entries"}
+          </span>
+          <button className="undo-button" onClick={handleUndo}>
+            Undo
+          </button>
+        </div>
+      )}
+
+      {contextMenu && (
+        <div
+          className="context-menu-overlay"
+          onClick={closeContextMenu}
+          onContextMenu={(e) => {
+            e.preventDefault();
+            closeContextMenu();
+          }}
+        >
+          <div
+            className="context-menu"
+            style={{
+              left: Math.min(contextMenu.x, window.innerWidth - 180),
+              top: Math.min(contextMenu.y, window.innerHeight - 140),
+            }}
+            onClick={(e) => e.stopPropagation()}
+          >
+            {contextMenu.entry.html && (
+              <button
+                className="context-menu-item"
+                onClick={() =>
+                  handleCopy(
+                    contextMenu.entry,
+                    contextMenu.index,
+                    undefined,
+                    true
+                  )
+                }
+              >
+                Copy with formatting
+              </button>
+            )}
+            {TRANSFORMS.filter(
+              ({ transform }) =>
+                transform !== "PrettyJson" || jsonIds.has(contextMenu.entry.id)
+            ).map(({ transform, label }) => (
+              <button
+                key={transform}
+                className="context-menu-item"
+                onClick={() =>
+                  handleCopy(
+                    contextMenu.entry,
+                    contextMenu.index,
+                    transform
+                  )
+                }
+              >
+                {label}
+              </button>
+            ))}
+            {transformError && (
+              <div className="context-menu-error">{transformError}</div>
+            )}
+          </div>
+        </div>
+      )}
+
       {showClearConfirm && (
         <div className="confirm-overlay" onClick={cancelClearAll}>
           <div